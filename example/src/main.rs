@@ -1,13 +1,12 @@
 extern crate core;
 
-use std::collections::HashMap;
 use std::default::Default;
 use glium::{Blend, DrawParameters, implement_vertex, program, Surface, uniform};
 use glium::index::{NoIndices, PrimitiveType};
 use glutin::dpi::PhysicalSize;
 use glam::Mat4;
 use glutin::event::MouseScrollDelta;
-use artery_font::{ArteryFont, CodepointType, ImageType, PixelFormat, Rect};
+use artery_font::{ArteryFont, CodepointType, GlyphQuad, ImageType, LayoutOptions, PixelFormat};
 
 #[derive(Debug, Copy, Clone)]
 struct Vertex {
@@ -16,39 +15,16 @@ struct Vertex {
 }
 implement_vertex!(Vertex, position, tex_coord);
 
-#[derive(Debug, Copy, Clone)]
-struct Quad {
-    plane_bounds: Rect,
-    atlas_bounds: Rect
-}
-
-impl Quad {
-
-    fn vertices(&self, x_offset: f32, y_offset: f32) -> impl Iterator<Item=Vertex> {
-        [
-            Vertex { position: [x_offset + self.plane_bounds.left , y_offset + self.plane_bounds.bottom], tex_coord: [self.atlas_bounds.left , self.atlas_bounds.bottom] },
-            Vertex { position: [x_offset + self.plane_bounds.right, y_offset + self.plane_bounds.bottom], tex_coord: [self.atlas_bounds.right, self.atlas_bounds.bottom] },
-            Vertex { position: [x_offset + self.plane_bounds.left , y_offset + self.plane_bounds.top   ], tex_coord: [self.atlas_bounds.left , self.atlas_bounds.top   ] },
-            Vertex { position: [x_offset + self.plane_bounds.left , y_offset + self.plane_bounds.top   ], tex_coord: [self.atlas_bounds.left , self.atlas_bounds.top   ] },
-            Vertex { position: [x_offset + self.plane_bounds.right, y_offset + self.plane_bounds.bottom], tex_coord: [self.atlas_bounds.right, self.atlas_bounds.bottom] },
-            Vertex { position: [x_offset + self.plane_bounds.right, y_offset + self.plane_bounds.top   ], tex_coord: [self.atlas_bounds.right, self.atlas_bounds.top   ] },
-        ].into_iter()
-    }
-
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Glyph {
-    advance: f32,
-    quad: Option<Quad>
-}
-
-impl Glyph {
-
-    fn vertices(&self, x_offset: f32, y_offset: f32) -> impl Iterator<Item=Vertex> + '_ {
-        self.quad.iter().flat_map(move |q|q.vertices(x_offset, y_offset))
-    }
-
+fn quad_vertices(quad: &GlyphQuad, x_offset: f32, y_offset: f32) -> [Vertex; 6] {
+    let (plane, uv) = (quad.plane, quad.uv);
+    [
+        Vertex { position: [x_offset + plane.left , y_offset + plane.bottom], tex_coord: [uv.left , uv.bottom] },
+        Vertex { position: [x_offset + plane.right, y_offset + plane.bottom], tex_coord: [uv.right, uv.bottom] },
+        Vertex { position: [x_offset + plane.left , y_offset + plane.top   ], tex_coord: [uv.left , uv.top   ] },
+        Vertex { position: [x_offset + plane.left , y_offset + plane.top   ], tex_coord: [uv.left , uv.top   ] },
+        Vertex { position: [x_offset + plane.right, y_offset + plane.bottom], tex_coord: [uv.right, uv.bottom] },
+        Vertex { position: [x_offset + plane.right, y_offset + plane.top   ], tex_coord: [uv.right, uv.top   ] },
+    ]
 }
 
 fn main() {
@@ -60,61 +36,31 @@ fn main() {
     let cb = glutin::ContextBuilder::new();
     let display = glium::Display::new(wb, cb, &event_loop).unwrap();
 
-    let (opengl_texture, glyphs, line_height, px_range) = {
+    let (opengl_texture, layouter, px_range) = {
         let arfont = ArteryFont::read(&include_bytes!("../data/test.arfont")[..]).unwrap();
         let image = arfont.images.first().unwrap();
         let variant = arfont.variants.first().unwrap();
         assert_eq!(variant.image_type, ImageType::Msdf);
         assert_eq!(variant.codepoint_type, CodepointType::Unicode);
-        let line_height = variant.metrics.line_height;
         let pxrange = variant.metrics.distance_range / variant.metrics.font_size;
-
-        let mut glyphs = HashMap::<char, Glyph>::new();
-
-        for glyph in &variant.glyphs {
-            let unicode = std::char::from_u32(glyph.codepoint).unwrap();
-            let advance = glyph.advance.horizontal;
-            assert_eq!(glyph.advance.vertical, 0.0, "character: {}", unicode);
-            assert_eq!(glyph.image, 0);
-            glyphs.insert(unicode, Glyph {
-                advance,
-                quad: match glyph.is_drawable() {
-                    true => Some(Quad {
-                        plane_bounds: glyph.plane_bounds,
-                        atlas_bounds: glyph.image_bounds.scaled(1.0 / image.width as f32, 1.0 / image.height as f32)
-                    }),
-                    false => None
-                }
-            });
-        }
+        let layouter = arfont.layouter(0).unwrap();
 
         assert_eq!(image.channels, 3);
         assert_eq!(image.pixel_format, PixelFormat::Unsigned8);
         let image = glium::texture::RawImage2d::from_raw_rgb(image.data.clone(), (image.width, image.height));
         let opengl_texture = glium::texture::Texture2d::new(&display, image).unwrap();
-        (opengl_texture, glyphs, line_height, pxrange)
+        (opengl_texture, layouter, pxrange)
     };
 
     let text = "Hello World!\nThis an example for text rendering\nusing msdf fonts";
 
     // building the vertex buffer, which contains all the vertices that we will draw
     let vertex_buffer = {
-
-        let mut vertices = Vec::new();
-        let mut x;
-        let mut y = 2.2;
-
-        for line in text.lines() {
-            x = 0.2;
-            for glyph in line.chars().map(|c|glyphs[&c]) {
-                for v in glyph.vertices(x, y) {
-                    vertices.push(v);
-                }
-                x += glyph.advance;
-            }
-            y -= line_height * 0.55;
-        }
-
+        let layout = layouter.layout(text, &LayoutOptions::default());
+        let vertices: Vec<Vertex> = layout
+            .quads()
+            .flat_map(|quad| quad_vertices(quad, 0.2, 6.0))
+            .collect();
 
         glium::VertexBuffer::new(&display,
                                  vertices.as_slice()
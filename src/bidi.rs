@@ -0,0 +1,211 @@
+//! The implicit part of the unicode bidirectional algorithm (UAX #9), without explicit embeddings, overrides and isolates.
+
+/// The bidirectional character types, named as in UAX #9.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Class {
+    /// Left to right letters
+    L,
+    /// Hebrew letters
+    R,
+    /// Arabic letters
+    AL,
+    /// European digits
+    EN,
+    /// Arabic digits
+    AN,
+    /// Plus and minus signs
+    ES,
+    /// Currency and percent signs that belong to a number
+    ET,
+    /// Separators within numbers
+    CS,
+    /// Combining marks
+    NSM,
+    WS,
+    /// Other neutral characters
+    ON
+}
+
+fn class(c: char) -> Class {
+    match c {
+        '0'..='9' | '\u{06F0}'..='\u{06F9}' => Class::EN,
+        '\u{0660}'..='\u{0669}' | '\u{066B}' | '\u{066C}' => Class::AN,
+        '+' | '-' => Class::ES,
+        '#' | '$' | '%' | '\u{00A2}'..='\u{00A5}' | '\u{00B0}' | '\u{20A0}'..='\u{20CF}' | '\u{2030}' => Class::ET,
+        ',' | '.' | '/' | ':' | '\u{00A0}' => Class::CS,
+        '\u{0300}'..='\u{036F}' | '\u{0591}'..='\u{05BD}' | '\u{05BF}' | '\u{05C1}' | '\u{05C2}' | '\u{05C4}' | '\u{05C5}' | '\u{05C7}'
+        | '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06DC}' | '\u{06DF}'..='\u{06E4}'
+        | '\u{06E7}' | '\u{06E8}' | '\u{06EA}'..='\u{06ED}' | '\u{200C}' | '\u{200D}' | '\u{20D0}'..='\u{20FF}' | '\u{FE00}'..='\u{FE0F}' => Class::NSM,
+        '\u{0590}'..='\u{05FF}' | '\u{07C0}'..='\u{085F}' | '\u{200F}' | '\u{FB1D}'..='\u{FB4F}' => Class::R,
+        '\u{0600}'..='\u{07BF}' | '\u{0860}'..='\u{08FF}' | '\u{FB50}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFE}' => Class::AL,
+        c if c.is_whitespace() => Class::WS,
+        c if c.is_alphanumeric() || c == '\u{200E}' => Class::L,
+        _ => Class::ON
+    }
+}
+
+/// Whether `text` contains any characters that are written right to left, which means that it has to be reordered.
+pub fn has_right_to_left(text: &str) -> bool {
+    text.chars().any(|c| matches!(class(c), Class::R | Class::AL | Class::AN))
+}
+
+/// Resolves the embedding levels of a paragraph. Odd levels are written right to left.
+///
+/// Returns the level of the paragraph and the level of every byte of `text`.
+pub fn paragraph_levels(text: &str) -> (u8, Vec<u8>) {
+    let mut classes: Vec<Class> = text.chars().map(class).collect();
+    let paragraph = classes
+        .iter()
+        .find_map(|class| match class {
+            Class::L => Some(0),
+            Class::R | Class::AL => Some(1),
+            _ => None
+        })
+        .unwrap_or(0);
+    let embedding = match paragraph {
+        0 => Class::L,
+        _ => Class::R
+    };
+    resolve_weak_types(&mut classes, embedding);
+    resolve_neutral_types(&mut classes, embedding);
+
+    let mut levels = Vec::with_capacity(text.len());
+    for (c, class) in text.chars().zip(classes) {
+        let level = match (paragraph, class) {
+            (0, Class::R) => 1,
+            (0, Class::AN | Class::EN) => 2,
+            (0, _) => 0,
+            (_, Class::L | Class::AN | Class::EN) => 2,
+            (_, _) => 1
+        };
+        levels.resize(levels.len() + c.len_utf8(), level);
+    }
+    (paragraph, levels)
+}
+
+/// Rules W1 to W7.
+fn resolve_weak_types(classes: &mut [Class], embedding: Class) {
+    let mut previous = embedding;
+    let mut strong = embedding;
+    for class in classes.iter_mut() {
+        if *class == Class::NSM {
+            *class = previous;
+        }
+        match *class {
+            Class::L | Class::R | Class::AL => strong = *class,
+            Class::EN if strong == Class::AL => *class = Class::AN,
+            _ => {}
+        }
+        previous = *class;
+        if *class == Class::AL {
+            *class = Class::R;
+        }
+    }
+    for i in 1..classes.len().saturating_sub(1) {
+        let (before, after) = (classes[i - 1], classes[i + 1]);
+        classes[i] = match classes[i] {
+            Class::ES if before == Class::EN && after == Class::EN => Class::EN,
+            Class::CS if before == after && matches!(before, Class::EN | Class::AN) => before,
+            class => class
+        };
+    }
+    let mut i = 0;
+    while i < classes.len() {
+        let end = run_end(classes, i, |class| class == Class::ET);
+        if end > i {
+            if (i > 0 && classes[i - 1] == Class::EN) || classes.get(end) == Some(&Class::EN) {
+                classes[i..end].fill(Class::EN);
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    let mut strong = embedding;
+    for class in classes.iter_mut() {
+        match *class {
+            Class::ES | Class::ET | Class::CS => *class = Class::ON,
+            Class::L | Class::R => strong = *class,
+            Class::EN if strong == Class::L => *class = Class::L,
+            _ => {}
+        }
+    }
+}
+
+/// Rules N1 and N2. Neutrals between characters of the same direction take that direction, all other neutrals the embedding direction.
+fn resolve_neutral_types(classes: &mut [Class], embedding: Class) {
+    let direction = |class: Class| match class {
+        Class::L => Class::L,
+        _ => Class::R
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        let end = run_end(classes, i, |class| matches!(class, Class::WS | Class::ON));
+        if end > i {
+            let before = match i {
+                0 => embedding,
+                _ => direction(classes[i - 1])
+            };
+            let after = classes.get(end).map_or(embedding, |&class| direction(class));
+            classes[i..end].fill(if before == after { before } else { embedding });
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+fn run_end(classes: &[Class], start: usize, member: impl Fn(Class) -> bool) -> usize {
+    classes[start..].iter().position(|&class| !member(class)).map_or(classes.len(), |length| start + length)
+}
+
+/// Computes the visual order of the characters of a line from their levels (rules L1 and L2).
+///
+/// Whitespace at the end of the line is reset to the paragraph level first.
+/// Returns the indices of the characters from left to right.
+pub fn visual_order(characters: &[char], levels: &mut [u8], paragraph: u8) -> Vec<usize> {
+    for (c, level) in characters.iter().zip(levels.iter_mut()).rev() {
+        if !c.is_whitespace() {
+            break;
+        }
+        *level = paragraph;
+    }
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = levels.iter().copied().filter(|level| level % 2 == 1).min().unwrap_or(highest + 1);
+    for level in (lowest_odd..=highest).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] >= level {
+                let end = order[i..].iter().position(|&j| levels[j] < level).map_or(order.len(), |length| i + length);
+                order[i..end].reverse();
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    order
+}
+
+/// The mirrored form of characters like brackets that is displayed in right to left runs (rule L4).
+pub fn mirrored(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '\u{00AB}' => '\u{00BB}',
+        '\u{00BB}' => '\u{00AB}',
+        '\u{2039}' => '\u{203A}',
+        '\u{203A}' => '\u{2039}',
+        '\u{2264}' => '\u{2265}',
+        '\u{2265}' => '\u{2264}',
+        c => c
+    }
+}
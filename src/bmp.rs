@@ -0,0 +1,188 @@
+use crate::enums::ImageEncoding;
+use crate::error::{Error, ErrorKind};
+use crate::{ensure_image_matches, flip_vertically};
+use crate::header::ImageHeader;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+/// Decodes an uncompressed 8, 24 or 32 bit BMP into tightly packed, bottom-up rows.
+pub fn decode(data: &[u8], image_header: &ImageHeader) -> Result<Vec<u8>, Error> {
+    ensure!(data.len() >= 54 && &data[..2] == b"BM", invalid("bad header"));
+    let pixel_offset = read_u32(data, 10) as usize;
+    let info_size = read_u32(data, 14) as usize;
+    ensure!(info_size >= 40 && data.len() >= 14 + info_size, invalid("unsupported info header"));
+
+    let width = read_u32(data, 18) as i32;
+    let height = read_u32(data, 22) as i32;
+    let bits = read_u16(data, 28);
+    let compression = read_u32(data, 30);
+    ensure!(width > 0 && height != 0, invalid("invalid dimensions"));
+    let (width, top_down, height) = (width as usize, height < 0, height.unsigned_abs() as usize);
+
+    let masks = match (compression, bits) {
+        (BI_RGB, 32) => [0x00ff0000, 0x0000ff00, 0x000000ff, 0xff000000],
+        (BI_BITFIELDS, 32) => {
+            ensure!(data.len() >= 66, invalid("truncated header"));
+            let alpha = match info_size {
+                40..=55 => 0,
+                _ => read_u32(data, 66)
+            };
+            let masks = [read_u32(data, 54), read_u32(data, 58), read_u32(data, 62), alpha];
+            ensure!(masks.iter().all(|&m| m == 0 || m.count_ones() == 8), invalid("unsupported bit fields"));
+            masks
+        },
+        (BI_RGB, 8) | (BI_RGB, 24) => [0; 4],
+        _ => fail!(invalid("unsupported compression or bits per pixel"))
+    };
+
+    let palette = match bits {
+        8 => {
+            let colors = match read_u32(data, 46) {
+                0 => 256,
+                n => n as usize
+            };
+            let offset = 14 + info_size;
+            ensure!(colors <= 256 && data.len() >= offset + 4 * colors, invalid("bad palette"));
+            data[offset..offset + 4 * colors]
+                .chunks_exact(4)
+                .map(|c| [c[2], c[1], c[0]])
+                .collect::<Vec<_>>()
+        },
+        _ => Vec::new()
+    };
+
+    let channels = match bits {
+        8 if palette.iter().all(|c| c[0] == c[1] && c[1] == c[2]) => 1,
+        8 | 24 => 3,
+        _ => 4
+    };
+    ensure_image_matches(image_header, width as u32, height as u32, channels, 8)?;
+
+    let stride = (width * bits as usize / 8 + 3) & !3;
+    ensure!(data.len() >= pixel_offset.saturating_add(stride.saturating_mul(height)), invalid("truncated pixel data"));
+
+    let row_length = width * channels;
+    let mut buf = Vec::with_capacity(row_length * height);
+    for row in data[pixel_offset..].chunks_exact(stride).take(height) {
+        let row = &row[..width * bits as usize / 8];
+        match bits {
+            8 => for &index in row {
+                let color = palette.get(index as usize).copied().unwrap_or_default();
+                buf.extend_from_slice(&color[..channels]);
+            },
+            24 => for pixel in row.chunks_exact(3) {
+                buf.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+            },
+            _ => for pixel in row.chunks_exact(4) {
+                let pixel = u32::from_le_bytes(pixel.try_into().unwrap());
+                buf.extend(masks.iter().map(|&mask| match mask {
+                    0 => 0xff,
+                    mask => ((pixel & mask) >> mask.trailing_zeros()) as u8
+                }));
+            }
+        }
+    }
+
+    if top_down {
+        flip_vertically(&mut buf, row_length);
+    }
+    Ok(buf)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn invalid(reason: &'static str) -> ErrorKind {
+    ErrorKind::InvalidImage { encoding: ImageEncoding::Bmp, reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::PixelFormat;
+    use crate::header::test_image_header;
+
+    /// A BMP with a 40 byte info header, `palette` and `rows` stored as they are.
+    fn bmp(width: i32, height: i32, bits: u16, palette: &[[u8; 4]], rows: &[u8]) -> Vec<u8> {
+        let pixel_offset = 54 + 4 * palette.len() as u32;
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&(pixel_offset + rows.len() as u32).to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&pixel_offset.to_le_bytes());
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bits.to_le_bytes());
+        data.extend_from_slice(&BI_RGB.to_le_bytes());
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&(palette.len() as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        palette.iter().for_each(|color| data.extend_from_slice(color));
+        data.extend_from_slice(rows);
+        data
+    }
+
+    fn reason(result: Result<Vec<u8>, Error>) -> &'static str {
+        match result.map_err(Error::into_kind) {
+            Err(ErrorKind::InvalidImage { encoding: ImageEncoding::Bmp, reason }) => reason,
+            other => panic!("expected an invalid image, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn rgb() {
+        // Two bottom-up rows of BGR pixels, each padded to four bytes
+        let data = bmp(1, 2, 24, &[], &[3, 2, 1, 0, 6, 5, 4, 0]);
+        let image = decode(&data, &test_image_header(1, 2, 3, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn top_down_rgba() {
+        let data = bmp(1, -2, 32, &[], &[3, 2, 1, 9, 6, 5, 4, 8]);
+        let image = decode(&data, &test_image_header(1, 2, 4, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [4, 5, 6, 8, 1, 2, 3, 9]);
+    }
+
+    #[test]
+    fn gray_palette() {
+        let palette = [[0, 0, 0, 0], [7, 7, 7, 0]];
+        let data = bmp(3, 1, 8, &palette, &[1, 0, 1, 0]);
+        let image = decode(&data, &test_image_header(3, 1, 1, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [7, 0, 7]);
+    }
+
+    #[test]
+    fn zero_size() {
+        let data = bmp(0, 2, 24, &[], &[]);
+        let result = decode(&data, &test_image_header(0, 2, 3, PixelFormat::Unsigned8));
+        assert_eq!(reason(result), "invalid dimensions");
+        let data = bmp(2, 0, 24, &[], &[]);
+        let result = decode(&data, &test_image_header(2, 0, 3, PixelFormat::Unsigned8));
+        assert_eq!(reason(result), "invalid dimensions");
+    }
+
+    #[test]
+    fn mismatched_header() {
+        let data = bmp(1, 2, 24, &[], &[3, 2, 1, 0, 6, 5, 4, 0]);
+        assert!(decode(&data, &test_image_header(2, 1, 3, PixelFormat::Unsigned8)).is_err());
+        assert!(decode(&data, &test_image_header(1, 2, 4, PixelFormat::Unsigned8)).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let data = bmp(1, 2, 24, &[], &[3, 2, 1, 0, 6, 5, 4, 0]);
+        let header = test_image_header(1, 2, 3, PixelFormat::Unsigned8);
+        for len in 0..data.len() {
+            assert!(decode(&data[..len], &header).is_err(), "{} bytes", len);
+        }
+    }
+
+}
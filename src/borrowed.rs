@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+use crate::enums::*;
+use crate::structs::*;
+
+/// Borrowed counterpart of [`FontVariant`].
+///
+/// Names, metadata, glyphs and kerning pairs point directly into the parsed buffer
+/// unless the data is misaligned, in which case they are copied.
+#[derive(Debug, Clone)]
+pub struct FontVariantRef<'a> {
+    pub flags: u32,
+    pub weight: u32,
+    pub codepoint_type: CodepointType,
+    pub image_type: ImageType,
+    pub fallback_variant: u32,
+    pub fallback_glyph: u32,
+    pub metrics: FontMetric,
+    pub name: Cow<'a, str>,
+    pub metadata: Cow<'a, str>,
+    pub glyphs: Cow<'a, [Glyph]>,
+    pub kern_pairs: Cow<'a, [KernPair]>
+}
+
+impl<'a> FontVariantRef<'a> {
+    pub fn into_owned(self) -> FontVariant {
+        FontVariant {
+            flags: self.flags,
+            weight: self.weight,
+            codepoint_type: self.codepoint_type,
+            image_type: self.image_type,
+            fallback_variant: self.fallback_variant,
+            fallback_glyph: self.fallback_glyph,
+            metrics: self.metrics,
+            name: self.name.into_owned(),
+            metadata: self.metadata.into_owned(),
+            glyphs: self.glyphs.into_owned(),
+            kern_pairs: self.kern_pairs.into_owned()
+        }
+    }
+}
+
+/// Borrowed counterpart of [`Image`].
+///
+/// The pixel data is only borrowed for raw images that are already stored in the requested orientation.
+/// Compressed or flipped images are decoded into an owned buffer.
+/// When parsed lazily, the undecoded image is borrowed instead.
+#[derive(Debug, Clone)]
+pub struct ImageRef<'a> {
+    pub flags: u32,
+    pub width: u32,
+    pub height: u32,
+    pub channels: u32,
+    pub pixel_format: PixelFormat,
+    pub image_type: ImageType,
+    pub child_images: u32,
+    pub texture_flags: u32,
+    pub metadata: Cow<'a, str>,
+    pub data: Cow<'a, [u8]>,
+    /// The row order of [`ImageRef::data`].
+    pub orientation: ImageOrientation,
+    pub encoded: Option<EncodedImageRef<'a>>
+}
+
+impl<'a> ImageRef<'a> {
+    pub fn into_owned(self) -> Image {
+        Image {
+            flags: self.flags,
+            width: self.width,
+            height: self.height,
+            channels: self.channels,
+            pixel_format: self.pixel_format,
+            image_type: self.image_type,
+            child_images: self.child_images,
+            texture_flags: self.texture_flags,
+            metadata: self.metadata.into_owned(),
+            data: self.data.into_owned(),
+            orientation: self.orientation,
+            encoded: self.encoded.map(EncodedImageRef::into_owned)
+        }
+    }
+}
+
+/// Borrowed counterpart of [`EncodedImage`].
+#[derive(Debug, Clone)]
+pub struct EncodedImageRef<'a> {
+    pub encoding: ImageEncoding,
+    pub orientation: ImageOrientation,
+    pub row_length: u32,
+    pub data: Cow<'a, [u8]>,
+    pub decoded_checksum: Option<u32>
+}
+
+impl<'a> EncodedImageRef<'a> {
+    pub fn into_owned(self) -> EncodedImage {
+        EncodedImage {
+            encoding: self.encoding,
+            orientation: self.orientation,
+            row_length: self.row_length,
+            data: self.data.into_owned(),
+            decoded_checksum: self.decoded_checksum
+        }
+    }
+}
+
+impl EncodedImage {
+    pub fn as_ref(&self) -> EncodedImageRef<'_> {
+        EncodedImageRef {
+            encoding: self.encoding,
+            orientation: self.orientation,
+            row_length: self.row_length,
+            data: Cow::Borrowed(&self.data),
+            decoded_checksum: self.decoded_checksum
+        }
+    }
+}
+
+/// Borrowed counterpart of [`Appendix`].
+#[derive(Debug, Clone)]
+pub struct AppendixRef<'a> {
+    pub metadata: Cow<'a, str>,
+    pub data: Cow<'a, [u8]>
+}
+
+impl<'a> AppendixRef<'a> {
+    pub fn into_owned(self) -> Appendix {
+        Appendix {
+            metadata: self.metadata.into_owned(),
+            data: self.data.into_owned()
+        }
+    }
+}
+
+/// Borrowed counterpart of [`MetadataFormat`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MetadataFormatRef<'a> {
+    None,
+    PlainText(Cow<'a, str>),
+    Json(Cow<'a, str>)
+}
+
+impl<'a> MetadataFormatRef<'a> {
+    pub fn into_owned(self) -> MetadataFormat {
+        match self {
+            MetadataFormatRef::None => MetadataFormat::None,
+            MetadataFormatRef::PlainText(text) => MetadataFormat::PlainText(text.into_owned()),
+            MetadataFormatRef::Json(json) => MetadataFormat::Json(json.into_owned())
+        }
+    }
+}
+
+/// An artery font that borrows its contents from a byte slice.
+///
+/// Created using [`ArteryFontRef::parse`]. Useful for fonts that are embedded using `include_bytes!`.
+#[derive(Debug, Clone)]
+pub struct ArteryFontRef<'a> {
+    pub metadata_format: MetadataFormatRef<'a>,
+    pub variants: Vec<FontVariantRef<'a>>,
+    pub images: Vec<ImageRef<'a>>,
+    pub appendices: Vec<AppendixRef<'a>>
+}
+
+impl<'a> ArteryFontRef<'a> {
+    pub fn into_owned(self) -> ArteryFont {
+        ArteryFont {
+            metadata_format: self.metadata_format.into_owned(),
+            variants: self.variants.into_iter().map(FontVariantRef::into_owned).collect(),
+            images: self.images.into_iter().map(ImageRef::into_owned).collect(),
+            appendices: self.appendices.into_iter().map(AppendixRef::into_owned).collect()
+        }
+    }
+}
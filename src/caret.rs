@@ -0,0 +1,188 @@
+use crate::header::Real;
+use crate::layout::{LayoutGlyph, LineLayout, TextLayout, WritingMode};
+use crate::structs::Rect;
+
+/// The position of the text cursor as computed by [`TextLayout::caret`].
+#[derive(Debug, Copy, Clone)]
+pub struct Caret {
+    /// The index of the line in [`TextLayout::lines`].
+    pub line: usize,
+    /// A line with zero width that spans from the descender to the ascender of the line, or across a vertical column.
+    pub rect: Rect
+}
+
+impl TextLayout {
+
+    /// The caret in front of the grapheme cluster at byte offset `index` of `text`, which has to be the text this layout was created from.
+    ///
+    /// Offsets inside a cluster, for example between a letter and its combining marks, are moved to the start of the cluster.
+    /// Offsets past the end of the text place the caret at the end of the last line.
+    /// A layout without lines, like [`TextLayout::default`], returns a caret with zero size at the origin.
+    pub fn caret(&self, text: &str, index: usize) -> Caret {
+        let index = cluster_start(text, index.min(text.len()));
+        let line_index = self.lines
+            .iter()
+            .rposition(|line| line.text.start <= index)
+            .unwrap_or(0);
+        let line = match self.lines.get(line_index) {
+            Some(line) => line,
+            None => return Caret {
+                line: 0,
+                rect: bytemuck::Zeroable::zeroed()
+            }
+        };
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+        let next = glyphs
+            .iter()
+            .filter(|glyph| glyph.index >= index)
+            .min_by_key(|glyph| glyph.index);
+        let position = match next {
+            // In front of the next glyph in logical order
+            Some(glyph) => self.edges(glyph, line).0,
+            // Behind the last glyph in logical order
+            None => match glyphs.iter().max_by_key(|glyph| glyph.index) {
+                Some(glyph) => self.edges(glyph, line).1,
+                None => self.along(line.start)
+            }
+        };
+        let half = (line.ascender - line.descender) / 2.0;
+        let rect = match self.writing_mode {
+            WritingMode::Horizontal => Rect {
+                left: position,
+                bottom: line.baseline + line.descender,
+                right: position,
+                top: line.baseline + line.ascender
+            },
+            WritingMode::VerticalRightToLeft => Rect {
+                left: line.baseline - half,
+                bottom: position,
+                right: line.baseline + half,
+                top: position
+            }
+        };
+        Caret {
+            line: line_index,
+            rect
+        }
+    }
+
+    /// The byte offset of `text` the caret should be placed at when the layout is clicked at `x`, `y`.
+    /// `text` has to be the text this layout was created from.
+    ///
+    /// Picks the closest line and the closest boundary between grapheme clusters on it, so combining marks are never separated from their base.
+    pub fn hit_test(&self, text: &str, x: Real, y: Real) -> usize {
+        let (across, along) = match self.writing_mode {
+            WritingMode::Horizontal => (y, x),
+            WritingMode::VerticalRightToLeft => (x, self.along(y))
+        };
+        let line = match self.lines.iter().min_by(|a, b| self.distance(a, across).total_cmp(&self.distance(b, across))) {
+            Some(line) => line,
+            None => return 0
+        };
+        let mut best = (Real::INFINITY, line.text.start);
+        for glyph in &self.glyphs[line.glyphs.clone()] {
+            let (leading, trailing) = self.edges(glyph, line);
+            let (leading, trailing) = (self.along(leading), self.along(trailing));
+            let start = cluster_start(text, glyph.index);
+            let end = cluster_end(text, glyph.index);
+            for (edge, index) in [(leading, start), (trailing, end)] {
+                let distance = (edge - along).abs();
+                if distance < best.0 {
+                    best = (distance, index);
+                }
+            }
+        }
+        let index = best.1;
+        if line.wrapped && index >= line.text.end {
+            // Behind the end of a wrapped line, but in front of its hanging whitespace
+            return text[..line.text.end].trim_end().len().max(line.text.start);
+        }
+        index
+    }
+
+    /// The positions of the edges of a glyph along its line in logical order, so the leading edge of right to left glyphs is their right edge.
+    fn edges(&self, glyph: &LayoutGlyph, line: &LineLayout) -> (Real, Real) {
+        let (start, end) = match self.writing_mode {
+            WritingMode::Horizontal => (glyph.x, glyph.x + glyph.advance),
+            // Inverts the centering of vertical glyphs in TextLayouter::push_line
+            WritingMode::VerticalRightToLeft => {
+                let top = glyph.y + glyph.advance / 2.0 + (line.ascender + line.descender) / 2.0;
+                (top, top - glyph.advance)
+            }
+        };
+        match glyph.right_to_left {
+            true => (end, start),
+            false => (start, end)
+        }
+    }
+
+    /// Converts an offset along the writing direction to a coordinate and vice versa.
+    fn along(&self, offset: Real) -> Real {
+        match self.writing_mode {
+            WritingMode::Horizontal => offset,
+            WritingMode::VerticalRightToLeft => -offset
+        }
+    }
+
+    /// The distance of a coordinate across the lines from the extent of `line`.
+    fn distance(&self, line: &LineLayout, across: Real) -> Real {
+        let (low, high) = match self.writing_mode {
+            WritingMode::Horizontal => (line.baseline + line.descender, line.baseline + line.ascender),
+            WritingMode::VerticalRightToLeft => {
+                let half = (line.ascender - line.descender) / 2.0;
+                (line.baseline - half, line.baseline + half)
+            }
+        };
+        (low - across).max(across - high).max(0.0)
+    }
+
+}
+
+/// The start of the grapheme cluster that contains byte offset `index`.
+fn cluster_start(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    while let Some(c) = text[index..].chars().next() {
+        let previous = match text[..index].chars().next_back() {
+            Some(previous) => previous,
+            None => break
+        };
+        if !continues_cluster(previous, c) {
+            break;
+        }
+        index -= previous.len_utf8();
+    }
+    index
+}
+
+/// The end of the grapheme cluster that starts at byte offset `index`.
+fn cluster_end(text: &str, index: usize) -> usize {
+    let mut characters = text[index..].char_indices().peekable();
+    let mut previous = match characters.next() {
+        Some((_, c)) => c,
+        None => return index
+    };
+    while let Some(&(offset, c)) = characters.peek() {
+        if !continues_cluster(previous, c) {
+            return index + offset;
+        }
+        previous = c;
+        characters.next();
+    }
+    text.len()
+}
+
+/// Whether `c` belongs to the same grapheme cluster as the character `previous` in front of it.
+///
+/// Covers combining marks, variation selectors, emoji modifiers, zero width joiner sequences and `\r\n`.
+fn continues_cluster(previous: char, c: char) -> bool {
+    previous == '\u{200D}' || (previous == '\r' && c == '\n') || matches!(c,
+        '\u{0300}'..='\u{036F}' | '\u{0483}'..='\u{0489}' | '\u{0591}'..='\u{05BD}' | '\u{05BF}' | '\u{05C1}' | '\u{05C2}'
+        | '\u{05C4}' | '\u{05C5}' | '\u{05C7}' | '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}'
+        | '\u{06D6}'..='\u{06DC}' | '\u{06DF}'..='\u{06E4}' | '\u{06E7}' | '\u{06E8}' | '\u{06EA}'..='\u{06ED}'
+        | '\u{0900}'..='\u{0903}' | '\u{093A}'..='\u{094F}' | '\u{0E31}' | '\u{0E34}'..='\u{0E3A}' | '\u{0E47}'..='\u{0E4E}'
+        | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{200C}' | '\u{200D}' | '\u{20D0}'..='\u{20FF}'
+        | '\u{3099}' | '\u{309A}' | '\u{FE00}'..='\u{FE0F}' | '\u{FE20}'..='\u{FE2F}' | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}' | '\u{E0100}'..='\u{E01EF}')
+}
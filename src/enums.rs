@@ -6,20 +6,15 @@ pub enum MetadataFormat {
     Json(String)
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub enum CodepointType {
+    #[default]
     Unspecified,
     Unicode,
     Indexed,
     Iconographic
 }
 
-impl Default for CodepointType {
-    fn default() -> Self {
-        Self::Unspecified
-    }
-}
-
 impl From<u32> for CodepointType {
     fn from(i: u32) -> Self {
         match i {
@@ -31,8 +26,20 @@ impl From<u32> for CodepointType {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+impl From<CodepointType> for u32 {
+    fn from(v: CodepointType) -> Self {
+        match v {
+            CodepointType::Unspecified => 0,
+            CodepointType::Unicode => 1,
+            CodepointType::Indexed => 2,
+            CodepointType::Iconographic => 14
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub enum ImageType {
+    #[default]
     None,
     SrgbImage,
     LinearMask,
@@ -44,12 +51,6 @@ pub enum ImageType {
     MixedContent
 }
 
-impl Default for ImageType {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl From<u32> for ImageType {
     fn from(i: u32) -> Self {
         match i {
@@ -66,8 +67,25 @@ impl From<u32> for ImageType {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+impl From<ImageType> for u32 {
+    fn from(v: ImageType) -> Self {
+        match v {
+            ImageType::None => 0,
+            ImageType::SrgbImage => 1,
+            ImageType::LinearMask => 2,
+            ImageType::MaskedSrgbImage => 3,
+            ImageType::Sdf => 4,
+            ImageType::Psdf => 5,
+            ImageType::Msdf => 6,
+            ImageType::Mtsdf => 7,
+            ImageType::MixedContent => 255
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub enum ImageEncoding {
+    #[default]
     UnknownEncoding,
     RawBinary,
     Bmp,
@@ -76,12 +94,6 @@ pub enum ImageEncoding {
     Tga
 }
 
-impl Default for ImageEncoding {
-    fn default() -> Self {
-        Self::UnknownEncoding
-    }
-}
-
 impl From<u32> for ImageEncoding {
     fn from(i: u32) -> Self {
         match i {
@@ -95,21 +107,29 @@ impl From<u32> for ImageEncoding {
     }
 }
 
+impl From<ImageEncoding> for u32 {
+    fn from(v: ImageEncoding) -> Self {
+        match v {
+            ImageEncoding::UnknownEncoding => 0,
+            ImageEncoding::RawBinary => 1,
+            ImageEncoding::Bmp => 4,
+            ImageEncoding::Tiff => 5,
+            ImageEncoding::Png => 8,
+            ImageEncoding::Tga => 9
+        }
+    }
+}
+
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub enum PixelFormat {
+    #[default]
     Unknown,
     Boolean1,
     Unsigned8,
     Float32
 }
 
-impl Default for PixelFormat {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 impl From<u32> for PixelFormat {
     fn from(i: u32) -> Self {
         match i {
@@ -121,6 +141,17 @@ impl From<u32> for PixelFormat {
     }
 }
 
+impl From<PixelFormat> for u32 {
+    fn from(v: PixelFormat) -> Self {
+        match v {
+            PixelFormat::Unknown => 0,
+            PixelFormat::Boolean1 => 1,
+            PixelFormat::Unsigned8 => 8,
+            PixelFormat::Float32 => 32
+        }
+    }
+}
+
 impl PixelFormat {
     pub fn bits(self) -> usize {
         match self {
@@ -132,19 +163,14 @@ impl PixelFormat {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub enum ImageOrientation {
     BottomUp = -1,
+    #[default]
     Unknown,
     TopDown = 1,
 }
 
-impl Default for ImageOrientation {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 impl From<i32> for ImageOrientation {
     fn from(i: i32) -> Self {
         match i {
@@ -154,3 +180,13 @@ impl From<i32> for ImageOrientation {
         }
     }
 }
+
+impl From<ImageOrientation> for i32 {
+    fn from(v: ImageOrientation) -> Self {
+        match v {
+            ImageOrientation::BottomUp => -1,
+            ImageOrientation::Unknown => 0,
+            ImageOrientation::TopDown => 1
+        }
+    }
+}
@@ -4,47 +4,200 @@ use std::string::FromUtf8Error as Utf8Error;
 use std::error::Error as StdError;
 #[cfg(feature = "png")]
 use png::DecodingError as PngError;
+use crate::enums::ImageEncoding;
+
+/// The part of the font file that was being processed when an error occurred.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Section {
+    Header,
+    Metadata,
+    /// The variant section as a whole.
+    Variants,
+    Variant(usize),
+    /// The image section as a whole.
+    Images,
+    Image(usize),
+    /// The appendix section as a whole.
+    Appendices,
+    Appendix(usize),
+    Footer
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Section::Header => write!(f, "header"),
+            Section::Metadata => write!(f, "metadata"),
+            Section::Variants => write!(f, "variant section"),
+            Section::Variant(index) => write!(f, "variant {}", index),
+            Section::Images => write!(f, "image section"),
+            Section::Image(index) => write!(f, "image {}", index),
+            Section::Appendices => write!(f, "appendix section"),
+            Section::Appendix(index) => write!(f, "appendix {}", index),
+            Section::Footer => write!(f, "footer")
+        }
+    }
+}
+
+/// Where in the font file an error occurred.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Location {
+    pub section: Section,
+    /// The number of bytes that were consumed when the error occurred.
+    pub offset: usize
+}
+
+/// A property of an embedded image that has to agree with its image header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ImageProperty {
+    Width,
+    Height,
+    Channels,
+    BitDepth
+}
 
 #[derive(Debug)]
-pub enum Error {
+pub enum ErrorKind {
     Io(IoError),
     Utf8(Utf8Error),
-    Decode(String),
     #[cfg(feature = "png")]
-    Png(PngError)
+    Png(PngError),
+    /// The header tag or the header or footer magic number is wrong.
+    BadMagic,
+    UnsupportedRealType(u32),
+    UnknownMetadataFormat(u32),
+    /// The header declares metadata of the given length but no metadata format.
+    UnexpectedMetadata(u32),
+    ChecksumMismatch {
+        /// The checksum stored in the footer.
+        expected: u32,
+        /// The checksum of the file contents.
+        actual: u32
+    },
+    /// A section or the whole file is longer or shorter than declared.
+    LengthMismatch {
+        expected: usize,
+        actual: usize
+    },
+    /// The image encoding is unknown or the corresponding feature is not enabled.
+    UnsupportedEncoding(ImageEncoding),
+    UnknownOrientation(i32),
+    /// The decoded image does not match its image header.
+    ImageMismatch {
+        property: ImageProperty,
+        expected: u32,
+        actual: u32
+    },
+    /// The embedded image is malformed or uses a feature that is not supported by the decoder.
+    InvalidImage {
+        encoding: ImageEncoding,
+        reason: &'static str
+    },
+    /// A section is too large to be written.
+    TooLarge(usize),
+    /// The file declares a section or requires an allocation that exceeds the limits of [`ReadOptions`](crate::ReadOptions).
+    AllocationLimit {
+        requested: usize,
+        limit: usize
+    }
 }
 
-impl Display for Error {
+impl Display for ErrorKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Io(err) => err.fmt(f),
-            Error::Utf8(err) => err.fmt(f),
-            Error::Decode(err) => write!(f, "Decoding Error: {}", err),
+            ErrorKind::Io(err) => err.fmt(f),
+            ErrorKind::Utf8(err) => err.fmt(f),
             #[cfg(feature = "png")]
-            Error::Png(err) => err.fmt(f),
+            ErrorKind::Png(err) => err.fmt(f),
+            ErrorKind::BadMagic => write!(f, "bad magic number"),
+            ErrorKind::UnsupportedRealType(code) => write!(f, "unsupported floating point type {:#x}", code),
+            ErrorKind::UnknownMetadataFormat(format) => write!(f, "unknown metadata format {}", format),
+            ErrorKind::UnexpectedMetadata(length) => write!(f, "unexpected metadata of length {} without a metadata format", length),
+            ErrorKind::ChecksumMismatch { expected, actual } => write!(f, "checksum mismatch: expected {:#010x}, got {:#010x}", expected, actual),
+            ErrorKind::LengthMismatch { expected, actual } => write!(f, "length mismatch: expected {} bytes, got {}", expected, actual),
+            ErrorKind::UnsupportedEncoding(encoding) => write!(f, "encoding {:?} not supported or enabled", encoding),
+            ErrorKind::UnknownOrientation(orientation) => write!(f, "unknown image orientation {}", orientation),
+            ErrorKind::ImageMismatch { property, expected, actual } => write!(f, "image {:?} mismatch: header says {}, image has {}", property, expected, actual),
+            ErrorKind::InvalidImage { encoding, reason } => write!(f, "invalid {:?} image: {}", encoding, reason),
+            ErrorKind::TooLarge(length) => write!(f, "{} bytes are too large for the artery font format", length),
+            ErrorKind::AllocationLimit { requested, limit } => write!(f, "allocation of {} bytes exceeds the limit of {} bytes", requested, limit)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    location: Option<Location>
+}
+
+impl Error {
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Where in the file the error occurred. `None` for errors that are not tied to a file position, like writing or lazily decoding images.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    pub(crate) fn at(mut self, section: Section, offset: usize) -> Self {
+        self.location.get_or_insert(Location { section, offset });
+        self
+    }
+
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{} (in {} at byte {})", self.kind, location.section, location.offset),
+            None => self.kind.fmt(f)
         }
     }
 }
 
 impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(err) => Some(err),
+            ErrorKind::Utf8(err) => Some(err),
+            #[cfg(feature = "png")]
+            ErrorKind::Png(err) => Some(err),
+            _ => None
+        }
+    }
+}
 
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            location: None
+        }
+    }
 }
 
 impl From<IoError> for Error {
     fn from(err: IoError) -> Self {
-        Self::Io(err)
+        ErrorKind::Io(err).into()
     }
 }
 
 impl From<Utf8Error> for Error {
     fn from(err: Utf8Error) -> Self {
-        Self::Utf8(err)
+        ErrorKind::Utf8(err).into()
     }
 }
 
 #[cfg(feature = "png")]
 impl From<PngError> for Error {
     fn from(err: PngError) -> Self {
-        Self::Png(err)
+        ErrorKind::Png(err).into()
     }
 }
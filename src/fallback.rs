@@ -0,0 +1,181 @@
+use crate::enums::CodepointType;
+use crate::index::{scan_glyphs, Codepoint, GlyphIndex, LookupError};
+use crate::structs::{ArteryFont, FontVariant, Glyph};
+use crate::borrowed::{ArteryFontRef, FontVariantRef};
+
+/// A glyph found by [`ArteryFont::resolve_glyph`] together with the variant that contains it.
+#[derive(Debug, Clone)]
+pub struct ResolvedGlyph<'a, V = FontVariant> {
+    /// The index of [`ResolvedGlyph::variant`] in the font.
+    pub variant_index: usize,
+    pub variant: &'a V,
+    pub glyph: &'a Glyph,
+    /// Whether the glyph is the `fallback_glyph` of the requested variant instead of the requested codepoint.
+    pub substituted: bool
+}
+
+/// The fields of owned and borrowed variants that are needed to follow fallbacks.
+trait Fallback {
+    fn codepoint_type(&self) -> CodepointType;
+    fn glyphs(&self) -> &[Glyph];
+    fn fallback_variant(&self) -> u32;
+    fn fallback_glyph(&self) -> u32;
+}
+
+impl Fallback for FontVariant {
+    fn codepoint_type(&self) -> CodepointType {
+        self.codepoint_type
+    }
+
+    fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    fn fallback_variant(&self) -> u32 {
+        self.fallback_variant
+    }
+
+    fn fallback_glyph(&self) -> u32 {
+        self.fallback_glyph
+    }
+}
+
+impl<'a> Fallback for FontVariantRef<'a> {
+    fn codepoint_type(&self) -> CodepointType {
+        self.codepoint_type
+    }
+
+    fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    fn fallback_variant(&self) -> u32 {
+        self.fallback_variant
+    }
+
+    fn fallback_glyph(&self) -> u32 {
+        self.fallback_glyph
+    }
+}
+
+/// The variant that follows `current` in the fallback chain starting at `start`,
+/// or `None` if the chain ends or would visit a variant for the second time.
+fn next_fallback<V: Fallback>(variants: &[V], start: usize, current: usize) -> Option<usize> {
+    let next = variants[current].fallback_variant() as usize;
+    if next >= variants.len() {
+        return None;
+    }
+    let mut visited = start;
+    loop {
+        if visited == next {
+            return None;
+        }
+        if visited == current {
+            return Some(next);
+        }
+        visited = variants[visited].fallback_variant() as usize;
+    }
+}
+
+/// Searches the variant at `start` and its fallback variants for `codepoint` using `find`, which looks up a codepoint in a variant.
+fn find_in_chain<'s, V: Fallback>(variants: &[V], start: usize, codepoint: Codepoint, find: &impl Fn(usize, Codepoint) -> Result<&'s Glyph, LookupError>) -> Option<(usize, &'s Glyph)> {
+    let mut current = Some(start);
+    while let Some(index) = current {
+        if let Ok(glyph) = find(index, codepoint) {
+            return Some((index, glyph));
+        }
+        current = next_fallback(variants, start, index);
+    }
+    None
+}
+
+fn resolve<'s, V: Fallback>(variants: &'s [V], variant: usize, codepoint: Codepoint, find: impl Fn(usize, Codepoint) -> Result<&'s Glyph, LookupError>) -> Result<ResolvedGlyph<'s, V>, LookupError> {
+    let requested = variants.get(variant).ok_or(LookupError::UnknownVariant(variant))?;
+    let found = find_in_chain(variants, variant, codepoint, &find)
+        .map(|found| (found, false))
+        .or_else(|| Codepoint::new(requested.codepoint_type(), requested.fallback_glyph())
+            .and_then(|fallback| find_in_chain(variants, variant, fallback, &find))
+            .map(|found| (found, true)));
+    match found {
+        Some(((variant_index, glyph), substituted)) => Ok(ResolvedGlyph {
+            variant_index,
+            variant: &variants[variant_index],
+            glyph,
+            substituted
+        }),
+        None => Err(find(variant, codepoint).err().unwrap_or(LookupError::Missing(codepoint)))
+    }
+}
+
+/// Looks up a codepoint in a variant with a linear search.
+fn find_linear<'s, V: Fallback>(variants: &'s [V]) -> impl Fn(usize, Codepoint) -> Result<&'s Glyph, LookupError> {
+    |index, codepoint| scan_glyphs(variants[index].codepoint_type(), variants[index].glyphs(), codepoint)
+}
+
+/// Resolves glyphs like [`ArteryFont::resolve_glyph`], but looks them up in a [`GlyphIndex`] of every variant.
+///
+/// Created using [`ArteryFont::glyph_resolver`]. Like the indices, the resolver does not reflect later changes to the glyphs.
+#[derive(Debug, Clone)]
+pub struct GlyphResolver<'a, V = FontVariant> {
+    variants: &'a [V],
+    indices: Vec<GlyphIndex>
+}
+
+impl<'a> GlyphResolver<'a> {
+
+    /// See [`ArteryFont::resolve_glyph`].
+    pub fn resolve(&self, variant: usize, codepoint: impl Into<Codepoint>) -> Result<ResolvedGlyph<'_>, LookupError> {
+        resolve(self.variants, variant, codepoint.into(), |index, codepoint| self.indices[index].glyph(codepoint))
+    }
+
+}
+
+impl<'a, 'b> GlyphResolver<'a, FontVariantRef<'b>> {
+
+    /// See [`ArteryFont::resolve_glyph`].
+    pub fn resolve(&self, variant: usize, codepoint: impl Into<Codepoint>) -> Result<ResolvedGlyph<'_, FontVariantRef<'b>>, LookupError> {
+        resolve(self.variants, variant, codepoint.into(), |index, codepoint| self.indices[index].glyph(codepoint))
+    }
+
+}
+
+impl ArteryFont {
+
+    /// Looks up `codepoint` in the variant at index `variant`.
+    ///
+    /// If the variant does not contain the codepoint, the chain of `fallback_variant`s is searched until it ends,
+    /// points to a variant that does not exist or would repeat itself. If none of them contain the codepoint,
+    /// the `fallback_glyph` of the requested variant is looked up the same way.
+    /// The error describes why the codepoint is missing in the requested variant.
+    ///
+    /// The variants are searched linearly like [`FontVariant::find_glyph`]. Use [`ArteryFont::glyph_resolver`] for repeated lookups.
+    pub fn resolve_glyph(&self, variant: usize, codepoint: impl Into<Codepoint>) -> Result<ResolvedGlyph<'_>, LookupError> {
+        resolve(&self.variants, variant, codepoint.into(), find_linear(&self.variants))
+    }
+
+    /// Indexes the glyphs of all variants for [`GlyphResolver::resolve`].
+    pub fn glyph_resolver(&self) -> GlyphResolver<'_> {
+        GlyphResolver {
+            variants: &self.variants,
+            indices: self.variants.iter().map(FontVariant::glyph_index).collect()
+        }
+    }
+
+}
+
+impl<'a> ArteryFontRef<'a> {
+
+    /// See [`ArteryFont::resolve_glyph`].
+    pub fn resolve_glyph(&self, variant: usize, codepoint: impl Into<Codepoint>) -> Result<ResolvedGlyph<'_, FontVariantRef<'a>>, LookupError> {
+        resolve(&self.variants, variant, codepoint.into(), find_linear(&self.variants))
+    }
+
+    /// See [`ArteryFont::glyph_resolver`].
+    pub fn glyph_resolver(&self) -> GlyphResolver<'_, FontVariantRef<'a>> {
+        GlyphResolver {
+            variants: &self.variants,
+            indices: self.variants.iter().map(FontVariantRef::glyph_index).collect()
+        }
+    }
+
+}
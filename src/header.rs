@@ -1,7 +1,10 @@
 use bytemuck::{Zeroable, Pod};
+use crate::structs::{Glyph, KernPair};
+use crate::util::{aligned_length, string_length};
 
 pub const ARTERY_FONT_HEADER_TAG: &[u8; 16] = b"ARTERY/FONT\0\0\0\0\0";
 pub const ARTERY_FONT_HEADER_MAGIC_NO: u32 = 0x4d276a5c;
+pub const ARTERY_FONT_VERSION: u32 = 1;
 pub const ARTERY_FONT_FOOTER_MAGIC_NO: u32 = 0x55ccb363;
 
 #[cfg(not(feature = "double"))]
@@ -26,6 +29,89 @@ impl TypeCode for f64 {
     }
 }
 
+/// The floating point type used by a font file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RealType {
+    F32,
+    F64
+}
+
+impl RealType {
+    pub fn from_type_code(code: u32) -> Option<Self> {
+        match code {
+            c if c == f32::type_code() => Some(Self::F32),
+            c if c == f64::type_code() => Some(Self::F64),
+            _ => None
+        }
+    }
+
+    pub fn native() -> Self {
+        Self::from_type_code(Real::type_code()).unwrap()
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            RealType::F32 => 4,
+            RealType::F64 => 8
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Field {
+    U8,
+    U32,
+    Real
+}
+
+impl Field {
+    pub fn size(self, real: RealType) -> usize {
+        match self {
+            Field::U8 => 1,
+            Field::U32 => 4,
+            Field::Real => real.size()
+        }
+    }
+}
+
+/// Describes the on-disk layout of a struct as runs of bytes, 32 bit integers and reals.
+///
+/// The in-memory layout must match the field order without padding.
+pub trait Layout: Zeroable + Pod {
+    const FIELDS: &'static [(Field, usize)];
+
+    fn file_size(real: RealType) -> usize {
+        Self::FIELDS
+            .iter()
+            .map(|&(field, count)| count * field.size(real))
+            .sum()
+    }
+}
+
+impl Layout for u32 {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 1)];
+}
+
+impl Layout for ArteryFontHeader {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U8, 16), (Field::U32, 24)];
+}
+
+impl Layout for ArteryFontFooter {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 7)];
+}
+
+impl Layout for FontVariantHeader {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 12), (Field::Real, 32), (Field::U32, 4)];
+}
+
+impl Layout for ImageHeader {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 16)];
+}
+
+impl Layout for AppendixHeader {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 2)];
+}
+
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C)]
 pub struct ArteryFontHeader {
@@ -98,3 +184,39 @@ pub struct AppendixHeader {
     pub metadata_length: u32,
     pub data_length: u32
 }
+
+impl FontVariantHeader {
+    /// The number of bytes that follow the header in the file: name, metadata, glyphs and kerning pairs.
+    pub fn payload_length(&self, real: RealType) -> usize {
+        string_length(self.name_length as usize)
+            .saturating_add(string_length(self.metadata_length as usize))
+            .saturating_add((self.glyph_count as usize).saturating_mul(Glyph::file_size(real)))
+            .saturating_add((self.kern_pair_count as usize).saturating_mul(KernPair::file_size(real)))
+    }
+}
+
+impl ImageHeader {
+    /// The number of bytes that follow the header in the file: metadata and image data.
+    pub fn payload_length(&self) -> usize {
+        string_length(self.metadata_length as usize) + aligned_length(self.data_length as usize)
+    }
+}
+
+impl AppendixHeader {
+    /// The number of bytes that follow the header in the file: metadata and appendix data.
+    pub fn payload_length(&self) -> usize {
+        string_length(self.metadata_length as usize) + aligned_length(self.data_length as usize)
+    }
+}
+
+/// An image header with the given dimensions and format, used to test the image decoders.
+#[cfg(all(test, any(feature = "bmp", feature = "tga", feature = "tiff")))]
+pub fn test_image_header(width: u32, height: u32, channels: u32, pixel_format: crate::PixelFormat) -> ImageHeader {
+    ImageHeader {
+        width,
+        height,
+        channels,
+        pixel_format: pixel_format.into(),
+        ..Zeroable::zeroed()
+    }
+}
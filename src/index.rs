@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::error::Error as StdError;
+use crate::enums::CodepointType;
+use crate::structs::{Advance, FontVariant, Glyph, KernPair};
+use crate::borrowed::FontVariantRef;
+
+/// A codepoint tagged with the kind of glyph identifier it is.
+///
+/// Characters convert into [`Codepoint::Unicode`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Codepoint {
+    Unicode(char),
+    /// A glyph index of the original font.
+    Index(u32),
+    /// An application defined icon identifier.
+    Icon(u32)
+}
+
+impl Codepoint {
+
+    /// The codepoint type of variants that contain this codepoint.
+    pub fn codepoint_type(self) -> CodepointType {
+        match self {
+            Codepoint::Unicode(_) => CodepointType::Unicode,
+            Codepoint::Index(_) => CodepointType::Indexed,
+            Codepoint::Icon(_) => CodepointType::Iconographic
+        }
+    }
+
+    /// Interprets a value stored in a variant with the given codepoint type, for example [`FontVariant::fallback_glyph`].
+    /// Values of variants with an unspecified codepoint type are treated as unicode if they are valid characters
+    /// and as glyph indices otherwise. Returns `None` for invalid unicode values.
+    pub fn new(codepoint_type: CodepointType, value: u32) -> Option<Self> {
+        match codepoint_type {
+            CodepointType::Unicode => char::from_u32(value).map(Codepoint::Unicode),
+            CodepointType::Indexed => Some(Codepoint::Index(value)),
+            CodepointType::Iconographic => Some(Codepoint::Icon(value)),
+            CodepointType::Unspecified => Some(char::from_u32(value).map_or(Codepoint::Index(value), Codepoint::Unicode))
+        }
+    }
+
+    /// The value that is stored in [`Glyph::codepoint`].
+    pub fn value(self) -> u32 {
+        match self {
+            Codepoint::Unicode(c) => c as u32,
+            Codepoint::Index(i) | Codepoint::Icon(i) => i
+        }
+    }
+
+}
+
+impl From<char> for Codepoint {
+    fn from(c: char) -> Self {
+        Codepoint::Unicode(c)
+    }
+}
+
+impl Display for Codepoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Codepoint::Unicode(c) => write!(f, "U+{:04X} {:?}", *c as u32, c),
+            Codepoint::Index(i) => write!(f, "glyph index {}", i),
+            Codepoint::Icon(i) => write!(f, "icon {:#x}", i)
+        }
+    }
+}
+
+/// The reason why a glyph could not be found.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LookupError {
+    /// The variant does not contain a glyph for the codepoint.
+    Missing(Codepoint),
+    /// The variant uses a different kind of codepoints, for example glyph indices instead of unicode.
+    CodepointTypeMismatch {
+        /// The codepoint type of the variant.
+        expected: CodepointType,
+        actual: CodepointType
+    },
+    /// The font does not contain a variant with this index.
+    UnknownVariant(usize)
+}
+
+impl Display for LookupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::Missing(codepoint) => write!(f, "no glyph for {}", codepoint),
+            LookupError::CodepointTypeMismatch { expected, actual } => write!(f, "codepoint type mismatch: variant uses {:?}, got {:?}", expected, actual),
+            LookupError::UnknownVariant(index) => write!(f, "no variant with index {}", index)
+        }
+    }
+}
+
+impl StdError for LookupError {}
+
+/// Checks that `codepoint` can be looked up in a variant with the given codepoint type.
+/// Variants with an unspecified codepoint type accept any codepoint.
+fn ensure_codepoint_type(codepoint_type: CodepointType, codepoint: Codepoint) -> Result<(), LookupError> {
+    match codepoint_type {
+        CodepointType::Unspecified => Ok(()),
+        expected if expected == codepoint.codepoint_type() => Ok(()),
+        expected => Err(LookupError::CodepointTypeMismatch { expected, actual: codepoint.codepoint_type() })
+    }
+}
+
+/// The glyphs of a variant sorted by codepoint for lookups in logarithmic time.
+///
+/// Created using [`FontVariant::glyph_index`]. The index holds a copy of the glyphs and
+/// does not reflect later changes to the variant.
+#[derive(Debug, Clone)]
+pub struct GlyphIndex {
+    codepoint_type: CodepointType,
+    glyphs: Vec<Glyph>
+}
+
+impl GlyphIndex {
+
+    /// Indexes `glyphs`. If a codepoint occurs more than once, the first glyph is used.
+    pub fn new(codepoint_type: CodepointType, glyphs: &[Glyph]) -> Self {
+        let mut glyphs = glyphs.to_vec();
+        glyphs.sort_by_key(|glyph| glyph.codepoint);
+        glyphs.dedup_by_key(|glyph| glyph.codepoint);
+        Self {
+            codepoint_type,
+            glyphs
+        }
+    }
+
+    pub fn codepoint_type(&self) -> CodepointType {
+        self.codepoint_type
+    }
+
+    pub fn glyph(&self, codepoint: impl Into<Codepoint>) -> Result<&Glyph, LookupError> {
+        let codepoint = codepoint.into();
+        ensure_codepoint_type(self.codepoint_type, codepoint)?;
+        self.glyphs
+            .binary_search_by_key(&codepoint.value(), |glyph| glyph.codepoint)
+            .map(|i| &self.glyphs[i])
+            .map_err(|_| LookupError::Missing(codepoint))
+    }
+
+    pub fn contains(&self, codepoint: impl Into<Codepoint>) -> bool {
+        self.glyph(codepoint).is_ok()
+    }
+
+    /// The indexed glyphs sorted by codepoint.
+    pub fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+}
+
+/// The kerning pairs of a variant hashed by codepoints for lookups in constant time.
+///
+/// Created using [`FontVariant::kerning_index`]. Queries do not allocate.
+#[derive(Debug, Clone)]
+pub struct KerningIndex {
+    codepoint_type: CodepointType,
+    pairs: HashMap<u64, Advance>
+}
+
+impl KerningIndex {
+
+    /// Indexes `kern_pairs`. If a pair occurs more than once, the first adjustment is used.
+    pub fn new(codepoint_type: CodepointType, kern_pairs: &[KernPair]) -> Self {
+        let mut pairs = HashMap::with_capacity(kern_pairs.len());
+        for pair in kern_pairs {
+            pairs
+                .entry(pair_key(pair.codepoint1, pair.codepoint2))
+                .or_insert(pair.advance);
+        }
+        Self {
+            codepoint_type,
+            pairs
+        }
+    }
+
+    /// The advance adjustment between `first` and `second`, or `None` if the pair is not kerned
+    /// or the codepoints do not match the codepoint type of the variant.
+    pub fn get(&self, first: impl Into<Codepoint>, second: impl Into<Codepoint>) -> Option<Advance> {
+        let (first, second) = (first.into(), second.into());
+        ensure_codepoint_type(self.codepoint_type, first).ok()?;
+        ensure_codepoint_type(self.codepoint_type, second).ok()?;
+        self.pairs
+            .get(&pair_key(first.value(), second.value()))
+            .copied()
+    }
+
+    /// Like [`KerningIndex::get`], but returns a zero adjustment for pairs that are not kerned.
+    pub fn kerning(&self, first: impl Into<Codepoint>, second: impl Into<Codepoint>) -> Advance {
+        self.get(first, second).unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+}
+
+/// Packs a pair of codepoints into a single key.
+fn pair_key(first: u32, second: u32) -> u64 {
+    (first as u64) << 32 | second as u64
+}
+
+pub(crate) fn scan_glyphs(codepoint_type: CodepointType, glyphs: &[Glyph], codepoint: Codepoint) -> Result<&Glyph, LookupError> {
+    ensure_codepoint_type(codepoint_type, codepoint)?;
+    glyphs
+        .iter()
+        .find(|glyph| glyph.codepoint == codepoint.value())
+        .ok_or(LookupError::Missing(codepoint))
+}
+
+impl FontVariant {
+
+    /// Looks up the glyph for `codepoint` with a linear search, which takes O(n) time in the number of glyphs.
+    /// Use [`FontVariant::glyph_index`] for repeated lookups, as [`TextLayouter`](crate::TextLayouter) and [`GlyphResolver`](crate::GlyphResolver) do.
+    pub fn find_glyph(&self, codepoint: impl Into<Codepoint>) -> Result<&Glyph, LookupError> {
+        scan_glyphs(self.codepoint_type, &self.glyphs, codepoint.into())
+    }
+
+    pub fn glyph_index(&self) -> GlyphIndex {
+        GlyphIndex::new(self.codepoint_type, &self.glyphs)
+    }
+
+    pub fn kerning_index(&self) -> KerningIndex {
+        KerningIndex::new(self.codepoint_type, &self.kern_pairs)
+    }
+
+}
+
+impl<'a> FontVariantRef<'a> {
+
+    /// See [`FontVariant::find_glyph`].
+    pub fn find_glyph(&self, codepoint: impl Into<Codepoint>) -> Result<&Glyph, LookupError> {
+        scan_glyphs(self.codepoint_type, &self.glyphs, codepoint.into())
+    }
+
+    pub fn glyph_index(&self) -> GlyphIndex {
+        GlyphIndex::new(self.codepoint_type, &self.glyphs)
+    }
+
+    pub fn kerning_index(&self) -> KerningIndex {
+        KerningIndex::new(self.codepoint_type, &self.kern_pairs)
+    }
+
+}
@@ -0,0 +1,645 @@
+use std::ops::Range;
+use crate::enums::{CodepointType, ImageOrientation};
+use crate::header::Real;
+use crate::index::{Codepoint, GlyphIndex, KerningIndex};
+use crate::structs::{ArteryFont, FontMetric, FontVariant, Glyph, KernPair, Rect};
+use crate::borrowed::{ArteryFontRef, FontVariantRef};
+use crate::bidi;
+
+/// The direction lines are written in.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum WritingMode {
+    /// Left to right lines that are stacked top to bottom.
+    #[default]
+    Horizontal,
+    /// Top to bottom columns that are stacked right to left, as used for Chinese and Japanese.
+    ///
+    /// Glyphs advance by [`Advance::vertical`](crate::Advance::vertical), or by one em if it is zero,
+    /// and are centered in columns that are `line_height` wide.
+    VerticalRightToLeft
+}
+
+/// The alignment of the lines of a [`TextLayout`]. For vertical text, `Left` aligns to the top and `Right` to the bottom.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches wrapped lines to the full width by widening their whitespace.
+    /// The last line of a paragraph and lines without whitespace are aligned left.
+    Justify
+}
+
+/// Settings for [`TextLayouter::layout`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LayoutOptions {
+    /// The size of one em in the output coordinate system, for example the font size in pixels.
+    pub size: Real,
+    /// The row order of the atlas texture the UVs are computed for. Should match [`ReadOptions::orientation`](crate::ReadOptions::orientation).
+    /// `Unknown` is treated like `BottomUp`.
+    pub orientation: ImageOrientation,
+    /// Lines that are wider than this are wrapped at the last possible line break. Words that do not fit on a line are broken.
+    /// For vertical text, this is the largest length of a column.
+    pub max_width: Option<Real>,
+    /// Lines are aligned within `max_width`, or within the widest line if it is not set.
+    pub align: TextAlign,
+    pub writing_mode: WritingMode
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            size: 1.0,
+            orientation: ImageOrientation::BottomUp,
+            max_width: None,
+            align: TextAlign::Left,
+            writing_mode: WritingMode::Horizontal
+        }
+    }
+}
+
+/// The part of the atlas that is drawn for a glyph.
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphQuad {
+    /// The index of the atlas image in [`ArteryFont::images`].
+    pub image: u32,
+    /// The corners of the quad in the layout coordinate system.
+    pub plane: Rect,
+    /// The texture coordinates of the corners of the quad, normalized to `0..=1`.
+    pub uv: Rect
+}
+
+/// A glyph placed by [`TextLayouter::layout`].
+#[derive(Debug, Copy, Clone)]
+pub struct LayoutGlyph {
+    pub character: char,
+    /// The character whose glyph is drawn, which is the mirrored form of [`LayoutGlyph::character`] for brackets in right to left runs.
+    pub shaped: char,
+    /// The byte offset of the character in the text.
+    pub index: usize,
+    /// The index of the line in [`TextLayout::lines`].
+    pub line: usize,
+    /// Whether the glyph is part of a right to left run. Such glyphs are drawn mirrored if they are brackets.
+    pub right_to_left: bool,
+    /// The horizontal position of the glyph origin.
+    pub x: Real,
+    /// The baseline the glyph is placed on.
+    pub y: Real,
+    /// The scaled advance along the line without kerning. Includes the extra space of justified whitespace.
+    pub advance: Real,
+    /// `None` for glyphs without a visible shape, like spaces.
+    pub quad: Option<GlyphQuad>
+}
+
+/// The metrics of a single line of a [`TextLayout`].
+#[derive(Debug, Clone)]
+pub struct LineLayout {
+    /// The byte range of the line in the text, without the line break.
+    pub text: Range<usize>,
+    /// The range of the line in [`TextLayout::glyphs`]. The glyphs are in visual order, from the start of the line to its end.
+    pub glyphs: Range<usize>,
+    /// Whether the paragraph of the line is written right to left, which is the case if its first letter is right to left.
+    pub right_to_left: bool,
+    /// The vertical position of the baseline, or the horizontal position of the center of a vertical column.
+    pub baseline: Real,
+    /// The offset of the start of the line after alignment, to the right for horizontal lines and downwards for vertical columns.
+    pub start: Real,
+    /// The advance width of the line, or the length of a vertical column. Whitespace at the end of wrapped lines is not included.
+    pub width: Real,
+    /// The scaled [`FontMetric::ascender`], measured upwards from the baseline.
+    pub ascender: Real,
+    /// The scaled [`FontMetric::descender`], usually negative.
+    pub descender: Real,
+    /// Whether the line was wrapped because it is too long, rather than ending at a line break or the end of the text.
+    pub wrapped: bool
+}
+
+/// Text that has been broken into lines and positioned glyphs.
+///
+/// The coordinate system matches the plane bounds of the glyphs: `y` points upwards.
+/// The origin is the top left corner of the text, so the first baseline is at `-ascender` and all glyphs have negative `y` coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub glyphs: Vec<LayoutGlyph>,
+    pub lines: Vec<LineLayout>,
+    /// The advance width of the widest line before alignment, or the width of all columns of vertical text.
+    pub width: Real,
+    /// The distance from the ascender of the first line to the descender of the last line, or the length of the longest column of vertical text.
+    pub height: Real,
+    pub writing_mode: WritingMode
+}
+
+impl TextLayout {
+
+    /// Moves the lines along their direction within `width`.
+    fn align(&mut self, align: TextAlign, width: Real, mode: WritingMode) {
+        for line in &mut self.lines {
+            let extra = width - line.width;
+            let glyphs = &mut self.glyphs[line.glyphs.clone()];
+            match align {
+                TextAlign::Left => {},
+                TextAlign::Center => shift(glyphs, extra / 2.0, mode),
+                TextAlign::Right => shift(glyphs, extra, mode),
+                TextAlign::Justify => {
+                    // Only whitespace between visible glyphs is widened, trailing whitespace keeps hanging over the edge.
+                    let content = glyphs.iter().rposition(|glyph| !glyph.character.is_whitespace()).map_or(0, |i| i + 1);
+                    let gaps = glyphs[..content].iter().filter(|glyph| glyph.character.is_whitespace()).count();
+                    if !line.wrapped || gaps == 0 || extra <= 0.0 {
+                        continue;
+                    }
+                    let gap = extra / gaps as Real;
+                    let mut offset = 0.0;
+                    for (i, glyph) in glyphs.iter_mut().enumerate() {
+                        shift(std::slice::from_mut(glyph), offset, mode);
+                        if i < content && glyph.character.is_whitespace() {
+                            glyph.advance += gap;
+                            offset += gap;
+                        }
+                    }
+                    line.width = width;
+                    continue;
+                }
+            }
+            line.start = match align {
+                TextAlign::Center => extra / 2.0,
+                TextAlign::Right => extra,
+                _ => 0.0
+            };
+        }
+    }
+
+    /// The quads of all visible glyphs.
+    pub fn quads(&self) -> impl Iterator<Item = &GlyphQuad> + '_ {
+        self.glyphs.iter().filter_map(|glyph| glyph.quad.as_ref())
+    }
+
+}
+
+/// The size of a text as computed by [`TextLayouter::measure`], in the coordinate system of [`TextLayout`].
+#[derive(Debug, Copy, Clone)]
+pub struct TextMetrics {
+    /// See [`TextLayout::width`].
+    pub width: Real,
+    /// See [`TextLayout::height`].
+    pub height: Real,
+    /// The union of the plane bounds of all visible glyphs, or `None` if there are none.
+    pub ink_bounds: Option<Rect>,
+    pub line_count: usize
+}
+
+/// Lays out text using the glyphs, kerning pairs and metrics of a single font variant.
+///
+/// The layouter indexes the glyphs and kerning pairs once and can be reused for any number of texts.
+/// Characters that are missing in the variant are replaced by its `fallback_glyph` or skipped if that is missing too.
+///
+/// ```
+/// use artery_font::{ArteryFont, LayoutOptions};
+///
+/// # let data = include_bytes!("../example/data/test.arfont");
+/// let arfont = ArteryFont::read_lazy(&data[..])?;
+/// let layouter = arfont.layouter(0).unwrap();
+/// let layout = layouter.layout("Hello\nWorld", &LayoutOptions {
+///     size: 32.0,
+///     ..Default::default()
+/// });
+/// assert_eq!(layout.lines.len(), 2);
+/// for quad in layout.quads() {
+///     // emit the vertices of quad.plane with the texture coordinates of quad.uv
+/// }
+/// # Ok::<(), artery_font::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextLayouter {
+    metrics: FontMetric,
+    fallback: Option<Codepoint>,
+    glyphs: GlyphIndex,
+    kerning: KerningIndex,
+    atlas_sizes: Vec<(u32, u32)>
+}
+
+impl TextLayouter {
+
+    /// Creates a layouter for `variant`. `atlas_sizes` contains the width and height of the images the glyphs refer to.
+    /// Glyphs that refer to other images get no quad.
+    pub fn new(variant: &FontVariant, atlas_sizes: &[(u32, u32)]) -> Self {
+        Self::from_parts(variant.codepoint_type, variant.fallback_glyph, variant.metrics, &variant.glyphs, &variant.kern_pairs, atlas_sizes)
+    }
+
+    /// See [`TextLayouter::new`].
+    pub fn from_ref(variant: &FontVariantRef, atlas_sizes: &[(u32, u32)]) -> Self {
+        Self::from_parts(variant.codepoint_type, variant.fallback_glyph, variant.metrics, &variant.glyphs, &variant.kern_pairs, atlas_sizes)
+    }
+
+    fn from_parts(codepoint_type: CodepointType, fallback_glyph: u32, metrics: FontMetric, glyphs: &[Glyph], kern_pairs: &[KernPair], atlas_sizes: &[(u32, u32)]) -> Self {
+        let glyphs = GlyphIndex::new(codepoint_type, glyphs);
+        let fallback = Codepoint::new(codepoint_type, fallback_glyph)
+            .filter(|codepoint| glyphs.contains(*codepoint));
+        Self {
+            metrics,
+            fallback,
+            glyphs,
+            kerning: KerningIndex::new(codepoint_type, kern_pairs),
+            atlas_sizes: atlas_sizes.to_vec()
+        }
+    }
+
+    pub fn metrics(&self) -> &FontMetric {
+        &self.metrics
+    }
+
+    /// The factor that converts the plane coordinates of the font to the layout coordinates for `size`.
+    pub fn scale(&self, size: Real) -> Real {
+        size / self.em_size()
+    }
+
+    /// The size of one em in plane coordinates. Fonts without an em size are assumed to use normalized coordinates.
+    fn em_size(&self) -> Real {
+        match self.metrics.em_size > 0.0 {
+            true => self.metrics.em_size,
+            false => 1.0
+        }
+    }
+
+    /// The glyph that is drawn for `character`, which is the fallback glyph if the variant does not contain it.
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.glyph(character)
+            .ok()
+            .or_else(|| self.fallback.and_then(|fallback| self.glyphs.glyph(fallback).ok()))
+    }
+
+    /// The scaled kerning adjustment between two adjacent characters.
+    pub fn kerning(&self, first: char, second: char, size: Real) -> Real {
+        self.kerning.kerning(first, second).horizontal * self.scale(size)
+    }
+
+    /// Splits `text` at line breaks and positions its glyphs. `\r\n` is treated like `\n`.
+    ///
+    /// Paragraphs that contain hebrew or arabic text are reordered using the implicit rules of the unicode bidirectional algorithm,
+    /// so right to left runs are placed right to left while numbers keep their order. Arabic letters are not shaped.
+    ///
+    /// If [`LayoutOptions::max_width`] is set, lines that are too long are wrapped as well.
+    pub fn layout(&self, text: &str, options: &LayoutOptions) -> TextLayout {
+        let scale = self.scale(options.size);
+        let mut layout = TextLayout {
+            writing_mode: options.writing_mode,
+            ..Default::default()
+        };
+        let mut start = 0;
+        for line in text.split('\n') {
+            let end = start + line.strip_suffix('\r').unwrap_or(line).len();
+            let paragraph = &text[start..end];
+            let bidi = bidi::has_right_to_left(paragraph).then(|| bidi::paragraph_levels(paragraph));
+            let mut line_start = start;
+            loop {
+                let line_end = match options.max_width {
+                    Some(max_width) => {
+                        let levels = bidi.as_ref().map(|(_, levels)| &levels[line_start - start..end - start]);
+                        self.wrap(text, line_start..end, levels, scale, max_width, options.writing_mode)
+                    },
+                    None => end
+                };
+                let levels = bidi.as_ref().map(|(level, levels)| (*level, &levels[line_start - start..line_end - start]));
+                self.push_line(&mut layout, text, line_start..line_end, levels, line_end < end, options);
+                line_start = line_end;
+                if line_start >= end {
+                    break;
+                }
+            }
+            start += line.len() + 1;
+        }
+        if options.align != TextAlign::Left {
+            layout.align(options.align, options.max_width.unwrap_or(layout.width), options.writing_mode);
+        }
+        let line_height = self.metrics.line_height * scale;
+        match options.writing_mode {
+            WritingMode::Horizontal => {
+                layout.height = (self.metrics.ascender - self.metrics.descender) * scale + (layout.lines.len() - 1) as Real * line_height;
+            },
+            WritingMode::VerticalRightToLeft => {
+                // The columns are placed to the left of the origin, move them to its right
+                let width = layout.lines.len() as Real * line_height;
+                translate(&mut layout.glyphs, width, 0.0);
+                for line in &mut layout.lines {
+                    line.baseline += width;
+                }
+                layout.height = layout.width;
+                layout.width = width;
+            }
+        }
+        layout
+    }
+
+    /// Finds the end of the first line of `range` that fits into `max_width`.
+    ///
+    /// The line ends at the last break opportunity before the first glyph that does not fit. Whitespace is allowed to overflow.
+    /// If there is no break opportunity, the word is broken before the glyph, unless it is the first glyph of the line.
+    ///
+    /// `levels` contains the bidi levels of the bytes of `range`, so that right to left glyphs are mirrored and kerned in visual order like in [`TextLayouter::push_line`].
+    fn wrap(&self, text: &str, range: Range<usize>, levels: Option<&[u8]>, scale: Real, max_width: Real, mode: WritingMode) -> usize {
+        let mut x = 0.0;
+        let mut previous_glyph = None;
+        let mut previous_character = None;
+        let mut opportunity = None;
+        for (offset, character) in text[range.clone()].char_indices() {
+            let index = range.start + offset;
+            if previous_character.is_some_and(|previous| is_break_opportunity(previous, character)) {
+                opportunity = Some(index);
+            }
+            previous_character = Some(character);
+            let right_to_left = levels.is_some_and(|levels| levels[offset] % 2 == 1);
+            let shaped = shape(character, right_to_left);
+            let glyph = match self.glyph(shaped) {
+                Some(glyph) => glyph,
+                None => continue
+            };
+            x += match previous_glyph {
+                Some((previous, true)) if right_to_left => self.kerning_along(shaped, previous, mode) * scale,
+                Some((previous, _)) => self.kerning_along(previous, shaped, mode) * scale,
+                None => 0.0
+            };
+            x += self.advance_along(glyph, mode) * scale;
+            previous_glyph = Some((shaped, right_to_left));
+            if x > max_width && !character.is_whitespace() {
+                return match opportunity {
+                    Some(opportunity) => opportunity,
+                    None if index > range.start => index,
+                    None => index + character.len_utf8()
+                };
+            }
+        }
+        range.end
+    }
+
+    /// Positions the glyphs of a line. `bidi` contains the paragraph level and the levels of the bytes of the line if it has to be reordered.
+    fn push_line(&self, layout: &mut TextLayout, text: &str, range: Range<usize>, bidi: Option<(u8, &[u8])>, wrapped: bool, options: &LayoutOptions) {
+        let scale = self.scale(options.size);
+        let ascender = self.metrics.ascender * scale;
+        let descender = self.metrics.descender * scale;
+        let line_height = self.metrics.line_height * scale;
+        let baseline = match options.writing_mode {
+            WritingMode::Horizontal => -ascender - layout.lines.len() as Real * line_height,
+            WritingMode::VerticalRightToLeft => -(layout.lines.len() as Real + 0.5) * line_height
+        };
+        let content_end = range.start + text[range.clone()].trim_end().len();
+        let characters: Vec<(usize, char, bool)> = match bidi {
+            None => text[range.clone()]
+                .char_indices()
+                .map(|(offset, character)| (range.start + offset, character, false))
+                .collect(),
+            Some((paragraph, levels)) => {
+                let (indices, characters): (Vec<usize>, Vec<char>) = text[range.clone()]
+                    .char_indices()
+                    .map(|(offset, character)| (range.start + offset, character))
+                    .unzip();
+                let mut levels: Vec<u8> = indices.iter().map(|&index| levels[index - range.start]).collect();
+                bidi::visual_order(&characters, &mut levels, paragraph)
+                    .into_iter()
+                    .map(|i| (indices[i], characters[i], levels[i] % 2 == 1))
+                    .collect()
+            }
+        };
+
+        let glyph_start = layout.glyphs.len();
+        let mut pen = 0.0;
+        let mut hanging = 0.0;
+        let mut previous = None;
+        for (index, character, right_to_left) in characters {
+            let shaped = shape(character, right_to_left);
+            let glyph = match self.glyph(shaped) {
+                Some(glyph) => glyph,
+                None => continue
+            };
+            if let Some(previous) = previous {
+                pen += self.kerning_along(previous, shaped, options.writing_mode) * scale;
+            }
+            let advance = self.advance_along(glyph, options.writing_mode) * scale;
+            let (x, y) = match options.writing_mode {
+                WritingMode::Horizontal => (pen, baseline),
+                // Centers the glyph in its cell, assuming that its ink is centered between ascender and descender
+                WritingMode::VerticalRightToLeft => (baseline - glyph.advance.horizontal * scale / 2.0, -pen - advance / 2.0 - (ascender + descender) / 2.0)
+            };
+            layout.glyphs.push(LayoutGlyph {
+                character,
+                shaped,
+                index,
+                line: layout.lines.len(),
+                right_to_left,
+                x,
+                y,
+                advance,
+                quad: self.quad(glyph, x, y, scale, options.orientation)
+            });
+            pen += advance;
+            if index >= content_end {
+                hanging += advance;
+            }
+            previous = Some(shaped);
+        }
+        let right_to_left = bidi.is_some_and(|(paragraph, _)| paragraph % 2 == 1);
+        let width = match wrapped {
+            true => pen - hanging,
+            false => pen
+        };
+        if wrapped && right_to_left {
+            // The whitespace at the end of the line is on its left side
+            shift(&mut layout.glyphs[glyph_start..], -hanging, options.writing_mode);
+        }
+        layout.lines.push(LineLayout {
+            text: range,
+            glyphs: glyph_start..layout.glyphs.len(),
+            right_to_left,
+            baseline,
+            start: 0.0,
+            width,
+            ascender,
+            descender,
+            wrapped
+        });
+        layout.width = layout.width.max(width);
+    }
+
+    /// The unscaled advance of `glyph` along a line of the given writing mode.
+    fn advance_along(&self, glyph: &Glyph, mode: WritingMode) -> Real {
+        match mode {
+            WritingMode::Horizontal => glyph.advance.horizontal,
+            WritingMode::VerticalRightToLeft if glyph.advance.vertical != 0.0 => glyph.advance.vertical,
+            WritingMode::VerticalRightToLeft => self.em_size()
+        }
+    }
+
+    /// The unscaled kerning adjustment along a line of the given writing mode.
+    fn kerning_along(&self, first: char, second: char, mode: WritingMode) -> Real {
+        let kerning = self.kerning.kerning(first, second);
+        match mode {
+            WritingMode::Horizontal => kerning.horizontal,
+            WritingMode::VerticalRightToLeft => kerning.vertical
+        }
+    }
+
+    /// Measures `text` exactly as [`TextLayouter::layout`] would place it.
+    pub fn measure(&self, text: &str, options: &LayoutOptions) -> TextMetrics {
+        let layout = self.layout(text, options);
+        let scale = self.scale(options.size);
+        let ink_bounds = layout.glyphs
+            .iter()
+            .filter_map(|positioned| {
+                let bounds = self.glyph(positioned.shaped)?.plane_bounds;
+                match bounds.is_empty() {
+                    true => None,
+                    false => Some(translated(bounds.scaled(scale, scale), positioned.x, positioned.y))
+                }
+            })
+            .reduce(|a, b| Rect {
+                left: a.left.min(b.left),
+                bottom: a.bottom.min(b.bottom),
+                right: a.right.max(b.right),
+                top: a.top.max(b.top)
+            });
+        TextMetrics {
+            width: layout.width,
+            height: layout.height,
+            ink_bounds,
+            line_count: layout.lines.len()
+        }
+    }
+
+    fn quad(&self, glyph: &Glyph, x: Real, y: Real, scale: Real, orientation: ImageOrientation) -> Option<GlyphQuad> {
+        let &(width, height) = self.atlas_sizes.get(glyph.image as usize)?;
+        if !glyph.is_drawable() || width == 0 || height == 0 {
+            return None;
+        }
+        let uv = glyph.image_bounds.scaled(1.0 / width as Real, 1.0 / height as Real);
+        Some(GlyphQuad {
+            image: glyph.image,
+            plane: translated(glyph.plane_bounds.scaled(scale, scale), x, y),
+            uv: match orientation {
+                ImageOrientation::TopDown => Rect {
+                    bottom: 1.0 - uv.bottom,
+                    top: 1.0 - uv.top,
+                    ..uv
+                },
+                _ => uv
+            }
+        })
+    }
+
+}
+
+/// The character that is drawn for `character`, see [`LayoutGlyph::shaped`].
+fn shape(character: char, right_to_left: bool) -> char {
+    match right_to_left {
+        true => bidi::mirrored(character),
+        false => character
+    }
+}
+
+/// Moves `glyphs` forward along their line.
+fn shift(glyphs: &mut [LayoutGlyph], offset: Real, mode: WritingMode) {
+    match mode {
+        WritingMode::Horizontal => translate(glyphs, offset, 0.0),
+        WritingMode::VerticalRightToLeft => translate(glyphs, 0.0, -offset)
+    }
+}
+
+fn translate(glyphs: &mut [LayoutGlyph], x: Real, y: Real) {
+    for glyph in glyphs {
+        glyph.x += x;
+        glyph.y += y;
+        if let Some(quad) = &mut glyph.quad {
+            quad.plane = translated(quad.plane, x, y);
+        }
+    }
+}
+
+fn translated(rect: Rect, x: Real, y: Real) -> Rect {
+    Rect {
+        left: x + rect.left,
+        bottom: y + rect.bottom,
+        right: x + rect.right,
+        top: y + rect.top
+    }
+}
+
+impl FontVariant {
+
+    /// Measures a single use of `text` at `size` without wrapping. Use [`TextLayouter::measure`] to measure many texts
+    /// or wrapped text, as this indexes the glyphs of the variant on every call.
+    pub fn measure(&self, text: &str, size: Real) -> TextMetrics {
+        TextLayouter::new(self, &[]).measure(text, &LayoutOptions {
+            size,
+            ..Default::default()
+        })
+    }
+
+}
+
+impl<'a> FontVariantRef<'a> {
+
+    /// See [`FontVariant::measure`].
+    pub fn measure(&self, text: &str, size: Real) -> TextMetrics {
+        TextLayouter::from_ref(self, &[]).measure(text, &LayoutOptions {
+            size,
+            ..Default::default()
+        })
+    }
+
+}
+
+impl ArteryFont {
+
+    /// Creates a [`TextLayouter`] for the variant at index `variant` that computes UVs for the images of this font.
+    pub fn layouter(&self, variant: usize) -> Option<TextLayouter> {
+        let atlas_sizes: Vec<_> = self.images.iter().map(|image| (image.width, image.height)).collect();
+        self.variants.get(variant).map(|variant| TextLayouter::new(variant, &atlas_sizes))
+    }
+
+}
+
+impl<'a> ArteryFontRef<'a> {
+
+    /// See [`ArteryFont::layouter`].
+    pub fn layouter(&self, variant: usize) -> Option<TextLayouter> {
+        let atlas_sizes: Vec<_> = self.images.iter().map(|image| (image.width, image.height)).collect();
+        self.variants.get(variant).map(|variant| TextLayouter::from_ref(variant, &atlas_sizes))
+    }
+
+}
+
+/// Whether a line may be broken between `before` and `after`.
+///
+/// This implements the most common rules of the unicode line breaking algorithm:
+/// breaks are allowed after whitespace, after hyphens that are followed by a letter, after zero width spaces
+/// and around ideographs, but never before whitespace, closing punctuation or after opening punctuation.
+fn is_break_opportunity(before: char, after: char) -> bool {
+    if is_glue(before) || is_glue(after) || after.is_whitespace() || is_closing(after) || is_opening(before) {
+        return false;
+    }
+    match before {
+        '\u{200B}' => true,
+        '-' | '\u{00AD}' | '\u{2010}' | '\u{2012}' | '\u{2013}' | '\u{2014}' => after.is_alphabetic(),
+        c if c.is_whitespace() => true,
+        c => is_ideographic(c) || is_ideographic(after)
+    }
+}
+
+/// Characters that prevent a break on either side, like the no-break space.
+fn is_glue(c: char) -> bool {
+    matches!(c, '\u{00A0}' | '\u{2007}' | '\u{202F}' | '\u{2060}' | '\u{FEFF}')
+}
+
+fn is_opening(c: char) -> bool {
+    matches!(c, '(' | '[' | '{' | '\u{3008}' | '\u{300A}' | '\u{300C}' | '\u{300E}' | '\u{3010}' | '\u{FF08}')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(c, ')' | ']' | '}' | ',' | '.' | ':' | ';' | '!' | '?'
+        | '\u{3001}' | '\u{3002}' | '\u{3009}' | '\u{300B}' | '\u{300D}' | '\u{300F}' | '\u{3011}'
+        | '\u{FF01}' | '\u{FF09}' | '\u{FF0C}' | '\u{FF0E}' | '\u{FF1A}' | '\u{FF1B}' | '\u{FF1F}')
+}
+
+/// Characters of scripts that are written without spaces and may be broken between any two characters.
+fn is_ideographic(c: char) -> bool {
+    matches!(c, '\u{2E80}'..='\u{2FFF}' | '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}' | '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{3FFFF}')
+}
@@ -1,165 +1,785 @@
+macro_rules! fail {
+	($kind:expr) => {{
+		return Err(crate::error::Error::from($kind))
+	}};
+}
+
+macro_rules! ensure {
+	( $x:expr, $kind:expr) => {{
+		if !$x {
+			fail!($kind);
+		}
+	}};
+}
+
 mod enums;
 mod header;
 mod util;
 mod structs;
+mod borrowed;
+mod summary;
+mod index;
+mod fallback;
+mod layout;
+mod bidi;
+mod caret;
+mod reader;
+mod parser;
 mod error;
-#[cfg(not(feature = "no-checksum"))]
+mod options;
 mod crc32;
+#[cfg(feature = "bmp")]
+mod bmp;
+#[cfg(feature = "tga")]
+mod tga;
+#[cfg(feature = "tiff")]
+mod tiff;
 
-use std::io::{Read};
-use crate::util::ReadWrapper;
+use std::borrow::Cow;
+use std::io::{Read, Seek, Write};
+use std::mem::size_of;
+use std::ops::Range;
+use crate::util::{aligned_length, IoResult, ReadWrapper, SliceReader, Source, string_length, SWAP_BYTES, WriteWrapper};
 use crate::header::*;
-use crate::enums::{ImageEncoding, ImageOrientation};
-use crate::error::{Error};
+use crate::options::Budget;
+
+pub use crate::error::{Error, ErrorKind, ImageProperty, Location, Section};
+pub use crate::options::ReadOptions;
 
-pub use crate::enums::{CodepointType, ImageType, MetadataFormat, PixelFormat};
+pub use crate::enums::{CodepointType, ImageEncoding, ImageOrientation, ImageType, MetadataFormat, PixelFormat};
 pub use crate::structs::*;
+pub use crate::borrowed::*;
+pub use crate::summary::*;
+pub use crate::index::{Codepoint, GlyphIndex, KerningIndex, LookupError};
+pub use crate::fallback::{GlyphResolver, ResolvedGlyph};
+pub use crate::caret::Caret;
+pub use crate::layout::{GlyphQuad, LayoutGlyph, LayoutOptions, LineLayout, TextAlign, TextLayout, TextLayouter, TextMetrics, WritingMode};
+pub use crate::reader::ArteryFontReader;
+pub use crate::parser::{ArteryFontParser, ParseEvent};
+
+impl ArteryFont {
+
+    pub fn read<R: Read>(reader: R) -> Result<Self, Error> {
+        Self::read_with(reader, ReadOptions::default())
+    }
+
+    /// Reads the font without decoding the embedded images. They can be decoded later using [`Image::decode`].
+    pub fn read_lazy<R: Read>(reader: R) -> Result<Self, Error> {
+        Self::read_with(reader, ReadOptions {
+            decode_images: false,
+            ..Default::default()
+        })
+    }
+
+    /// Reads the font using the given [`ReadOptions`].
+    pub fn read_with<R: Read>(reader: R, options: ReadOptions) -> Result<Self, Error> {
+        parse(ReadWrapper::new(reader, options.verify_checksum), &options).map(ArteryFontRef::into_owned)
+    }
+
+    /// Reads only the headers of the font and seeks over glyphs, kerning pairs, metadata and images.
+    ///
+    /// The skipped data is not validated and the checksum is not verified.
+    /// Use [`ArteryFont::probe_stream`] for readers that can not seek.
+    pub fn probe<R: Read + Seek>(reader: R) -> Result<ArteryFontSummary, Error> {
+        probe(ReadWrapper::new(reader, false), &ReadOptions::default(), ReadWrapper::seek_forward).map(|index| index.summary)
+    }
+
+    /// Like [`ArteryFont::probe`], but for readers that can not seek, like network streams.
+    ///
+    /// The skipped data is read and discarded, so this reads the whole file.
+    pub fn probe_stream<R: Read>(reader: R) -> Result<ArteryFontSummary, Error> {
+        probe(ReadWrapper::new(reader, false), &ReadOptions::default(), |reader, len| reader.skip(len)).map(|index| index.summary)
+    }
+
+    /// Serializes the font into the artery font format.
+    ///
+    /// Images that still have their [`Image::encoded`] bytes are written unchanged, unless [`Image::data`] has been changed
+    /// since decoding, which is detected using [`EncodedImage::decoded_checksum`] without decoding the image again. All other images are stored as `RawBinary` data in the row order given by [`Image::orientation`].
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), Error> {
+
+        let mut writer = WriteWrapper::new(writer);
+
+        let (metadata_format, metadata) = match &self.metadata_format {
+            MetadataFormat::None => (0, ""),
+            MetadataFormat::PlainText(text) => (1, text.as_str()),
+            MetadataFormat::Json(json) => (2, json.as_str())
+        };
+
+        let variants_length = self.variants
+            .iter()
+            .map(|variant| size_of::<FontVariantHeader>()
+                + string_length(variant.name.len())
+                + string_length(variant.metadata.len())
+                + size_of::<Glyph>() * variant.glyphs.len()
+                + size_of::<KernPair>() * variant.kern_pairs.len())
+            .sum::<usize>();
+        let stored_images: Vec<_> = self.images.iter().map(stored_image).collect();
+        let images_length = self.images
+            .iter()
+            .zip(&stored_images)
+            .map(|(image, stored)| size_of::<ImageHeader>()
+                + string_length(image.metadata.len())
+                + aligned_length(stored.3.len()))
+            .sum::<usize>();
+        let appendices_length = self.appendices
+            .iter()
+            .map(|appendix| size_of::<AppendixHeader>()
+                + string_length(appendix.metadata.len())
+                + aligned_length(appendix.data.len()))
+            .sum::<usize>();
+
+        writer.write_struct(&ArteryFontHeader {
+            tag: *ARTERY_FONT_HEADER_TAG,
+            magic_no: ARTERY_FONT_HEADER_MAGIC_NO,
+            version: ARTERY_FONT_VERSION,
+            flags: 0,
+            real_type: Real::type_code(),
+            reserved: [0; 4],
+            metadata_format,
+            metadata_length: to_u32(metadata.len())?,
+            variant_count: to_u32(self.variants.len())?,
+            variants_length: to_u32(variants_length)?,
+            image_count: to_u32(self.images.len())?,
+            images_length: to_u32(images_length)?,
+            appendix_count: to_u32(self.appendices.len())?,
+            appendices_length: to_u32(appendices_length)?,
+            reserved2: [0; 8]
+        })?;
+        writer.write_string(metadata)?;
+
+        for variant in &self.variants {
+            let mut metrics = [0.0; 32];
+            metrics[..8].copy_from_slice(bytemuck::cast_slice(std::slice::from_ref(&variant.metrics)));
+            writer.write_struct(&FontVariantHeader {
+                flags: variant.flags,
+                weight: variant.weight,
+                codepoint_type: variant.codepoint_type.into(),
+                image_type: variant.image_type.into(),
+                fallback_variant: variant.fallback_variant,
+                fallback_glyph: variant.fallback_glyph,
+                reserved: [0; 6],
+                metrics,
+                name_length: to_u32(variant.name.len())?,
+                metadata_length: to_u32(variant.metadata.len())?,
+                glyph_count: to_u32(variant.glyphs.len())?,
+                kern_pair_count: to_u32(variant.kern_pairs.len())?
+            })?;
+            writer.write_string(&variant.name)?;
+            writer.write_string(&variant.metadata)?;
+            writer.write_struct_array(&variant.glyphs)?;
+            writer.write_struct_array(&variant.kern_pairs)?;
+        }
+
+        for (image, (encoding, orientation, row_length, data)) in self.images.iter().zip(&stored_images) {
+            writer.write_struct(&ImageHeader {
+                flags: image.flags,
+                encoding: (*encoding).into(),
+                width: image.width,
+                height: image.height,
+                channels: image.channels,
+                pixel_format: image.pixel_format.into(),
+                image_type: image.image_type.into(),
+                row_length: to_u32(*row_length)?,
+                orientation: (*orientation).into(),
+                child_images: image.child_images,
+                texture_flags: image.texture_flags,
+                reserved: [0; 3],
+                metadata_length: to_u32(image.metadata.len())?,
+                data_length: to_u32(data.len())?
+            })?;
+            writer.write_string(&image.metadata)?;
+            writer.write_all(data)?;
+            writer.realign()?;
+        }
+
+        for appendix in &self.appendices {
+            writer.write_struct(&AppendixHeader {
+                metadata_length: to_u32(appendix.metadata.len())?,
+                data_length: to_u32(appendix.data.len())?
+            })?;
+            writer.write_string(&appendix.metadata)?;
+            writer.write_all(&appendix.data)?;
+            writer.realign()?;
+        }
+
+        let total_length = writer.bytes_written() + size_of::<ArteryFontFooter>() + size_of::<u32>();
+        writer.write_struct(&ArteryFontFooter {
+            salt: 0,
+            magic_no: ARTERY_FONT_FOOTER_MAGIC_NO,
+            reserved: [0; 4],
+            total_length: to_u32(total_length)?
+        })?;
+        let checksum = writer.checksum();
+        writer.write_struct(&checksum)?;
+        writer.flush()?;
+
+        Ok(())
+    }
 
-macro_rules! fail {
-	($($arg:tt)*) => {{
-		return Err(Error::Decode(std::format!($($arg)*)))
-	}};
 }
 
-macro_rules! ensure {
-	( $x:expr, $($arg:tt)*) => {{
-		if !$x {
-			fail!($($arg)*);
-		}
-	}};
+impl<'a> ArteryFontRef<'a> {
+
+    /// Parses a font without copying the glyphs, kerning pairs, strings and raw images out of `data`.
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_with(data, ReadOptions::default())
+    }
+
+    /// Parses the font without decoding the embedded images. See [`ArteryFont::read_lazy`].
+    pub fn parse_lazy(data: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_with(data, ReadOptions {
+            decode_images: false,
+            ..Default::default()
+        })
+    }
+
+    /// Parses the font using the given [`ReadOptions`].
+    pub fn parse_with(data: &'a [u8], options: ReadOptions) -> Result<Self, Error> {
+        parse(SliceReader::new(data, options.verify_checksum), &options)
+    }
+
 }
 
-impl ArteryFont {
+fn parse<'a, S: Source<'a>>(mut reader: S, options: &ReadOptions) -> Result<ArteryFontRef<'a>, Error> {
+    let mut section = Section::Header;
+    parse_sections(&mut reader, &mut section, &mut Budget::new(options))
+        .map_err(|err| err.at(section, reader.bytes_read()))
+}
 
-    #[cfg(target_endian = "little")]
-    pub fn read<R: Read>(reader: R) -> Result<Self, Error> {
+fn parse_sections<'a, S: Source<'a>>(reader: &mut S, section: &mut Section, budget: &mut Budget) -> Result<ArteryFontRef<'a>, Error> {
 
-        let mut reader = ReadWrapper::new(reader);
-
-        let font_header = reader.read_struct::<ArteryFontHeader>()?;
-        ensure!(font_header.tag == *ARTERY_FONT_HEADER_TAG, "bad header");
-        ensure!(font_header.magic_no == ARTERY_FONT_HEADER_MAGIC_NO, "bad header");
-        ensure!(font_header.real_type == Real::type_code(), "floating point type mismatch. Consider activating/deactivating the double feature.");
-
-        let metadata_format = match font_header.metadata_format {
-            0 => {
-                ensure!(font_header.metadata_length == 0, "Unexpected ");
-                MetadataFormat::None
-            },
-            1 => MetadataFormat::PlainText(reader.read_string(font_header.metadata_length as usize)??),
-            2 => MetadataFormat::Json(reader.read_string(font_header.metadata_length as usize)??),
-            _ => fail!("Unknown metadata format!")
-        };
+    let options = budget.options();
+    let (font_header, real) = read_font_header(reader)?;
+    ensure_section_lengths(&font_header, section, options)?;
+    *section = Section::Header;
+    if let Some(remaining) = reader.remaining() {
+        let expected = string_length(font_header.metadata_length as usize)
+            + font_header.variants_length as usize
+            + font_header.images_length as usize
+            + font_header.appendices_length as usize
+            + ArteryFontFooter::file_size(real)
+            + u32::file_size(real);
+        ensure!(expected <= remaining, ErrorKind::LengthMismatch { expected: reader.bytes_read() + expected, actual: reader.bytes_read() + remaining });
+    }
+
+    *section = Section::Metadata;
+    let metadata_format = read_metadata(reader, &font_header, budget)?;
+
+    let variants_length = SectionLength::new(reader.bytes_read(), font_header.variants_length as usize);
+    let variant_count = font_header.variant_count as usize;
+    *section = Section::Variants;
+    variants_length.ensure_fits(reader.bytes_read(), variant_count.saturating_mul(FontVariantHeader::file_size(real)))?;
+    budget.allocate(variant_count * size_of::<FontVariantRef>())?;
+    let mut variants = Vec::with_capacity(variant_count);
+    for i in 0..variant_count {
+        *section = Section::Variant(i);
+        variants.push(read_variant(reader, real, &variants_length, budget)?);
+    }
+    *section = Section::Variants;
+    variants_length.ensure_complete(reader.bytes_read())?;
+
+    let images_length = SectionLength::new(reader.bytes_read(), font_header.images_length as usize);
+    let image_count = font_header.image_count as usize;
+    *section = Section::Images;
+    images_length.ensure_fits(reader.bytes_read(), image_count.saturating_mul(ImageHeader::file_size(real)))?;
+    budget.allocate(image_count * size_of::<ImageRef>())?;
+    let mut images = Vec::with_capacity(image_count);
+    for i in 0..image_count {
+        *section = Section::Image(i);
+        images.push(read_image(reader, real, &images_length, budget)?);
+    }
+    *section = Section::Images;
+    images_length.ensure_complete(reader.bytes_read())?;
+
+    let appendices_length = SectionLength::new(reader.bytes_read(), font_header.appendices_length as usize);
+    let appendix_count = font_header.appendix_count as usize;
+    *section = Section::Appendices;
+    appendices_length.ensure_fits(reader.bytes_read(), appendix_count.saturating_mul(AppendixHeader::file_size(real)))?;
+    budget.allocate(appendix_count * size_of::<AppendixRef>())?;
+    let mut appendices = Vec::with_capacity(appendix_count);
+    for i in 0..appendix_count {
+        *section = Section::Appendix(i);
+        appendices.push(read_appendix(reader, real, &appendices_length, budget)?);
+    }
+    *section = Section::Appendices;
+    appendices_length.ensure_complete(reader.bytes_read())?;
+
+    *section = Section::Footer;
+    let footer = reader.read_struct::<ArteryFontFooter>(real)?;
+    ensure!(footer.magic_no == ARTERY_FONT_FOOTER_MAGIC_NO, ErrorKind::BadMagic);
+
+    let checksum = reader.checksum();
+    let footer_checksum = reader.read_struct::<u32>(real)?;
+    if let Some(checksum) = checksum {
+        ensure!(checksum == footer_checksum, ErrorKind::ChecksumMismatch { expected: footer_checksum, actual: checksum });
+    }
+
+    ensure_length(footer.total_length as usize, reader.bytes_read())?;
+
+    Ok(ArteryFontRef {
+        metadata_format,
+        variants,
+        images,
+        appendices
+    })
+}
+
+/// Accounts for `length` bytes that are about to be read, unless `reader` borrows them.
+fn charge<'a, S: Source<'a>>(reader: &S, budget: &mut Budget, length: usize) -> Result<(), Error> {
+    match reader.allocates() {
+        true => budget.allocate(length),
+        false => Ok(())
+    }
+}
+
+fn read_metadata<'a, S: Source<'a>>(reader: &mut S, font_header: &ArteryFontHeader, budget: &mut Budget) -> Result<MetadataFormatRef<'a>, Error> {
+    charge(reader, budget, font_header.metadata_length as usize)?;
+    Ok(match font_header.metadata_format {
+        0 => {
+            ensure!(font_header.metadata_length == 0, ErrorKind::UnexpectedMetadata(font_header.metadata_length));
+            MetadataFormatRef::None
+        },
+        1 => MetadataFormatRef::PlainText(reader.read_string(font_header.metadata_length as usize)??),
+        2 => MetadataFormatRef::Json(reader.read_string(font_header.metadata_length as usize)??),
+        format => fail!(ErrorKind::UnknownMetadataFormat(format))
+    })
+}
+
+fn read_variant<'a, S: Source<'a>>(reader: &mut S, real: RealType, section_length: &SectionLength, budget: &mut Budget) -> Result<FontVariantRef<'a>, Error> {
+    let variant_header = reader.read_struct::<FontVariantHeader>(real)?;
+    section_length.ensure_fits(reader.bytes_read(), variant_header.payload_length(real))?;
+    let glyphs_length = variant_header.glyph_count as usize * Glyph::file_size(real);
+    let kern_pairs_length = variant_header.kern_pair_count as usize * KernPair::file_size(real);
+    for length in [variant_header.name_length as usize, variant_header.metadata_length as usize, glyphs_length, kern_pairs_length] {
+        charge(reader, budget, length)?;
+    }
+    Ok(FontVariantRef {
+        flags: variant_header.flags,
+        weight: variant_header.weight,
+        codepoint_type: CodepointType::from(variant_header.codepoint_type),
+        image_type: ImageType::from(variant_header.image_type),
+        fallback_variant: variant_header.fallback_variant,
+        fallback_glyph: variant_header.fallback_glyph,
+        metrics: bytemuck::cast_slice(&variant_header.metrics[..8])[0],
+        name: reader.read_string(variant_header.name_length as usize)??,
+        metadata: reader.read_string(variant_header.metadata_length as usize)??,
+        glyphs: reader.read_struct_array(variant_header.glyph_count as usize, real)?,
+        kern_pairs: reader.read_struct_array(variant_header.kern_pair_count as usize, real)?
+    })
+}
+
+fn read_image<'a, S: Source<'a>>(reader: &mut S, real: RealType, section_length: &SectionLength, budget: &mut Budget) -> Result<ImageRef<'a>, Error> {
+    let image_header = reader.read_struct::<ImageHeader>(real)?;
+    section_length.ensure_fits(reader.bytes_read(), image_header.payload_length())?;
+    charge(reader, budget, image_header.metadata_length as usize)?;
+    charge(reader, budget, image_header.data_length as usize)?;
+    let pixel_format = PixelFormat::from(image_header.pixel_format);
+    let metadata = reader.read_string(image_header.metadata_length as usize)??;
+    let encoded = EncodedImageRef {
+        encoding: ImageEncoding::from(image_header.encoding),
+        orientation: ImageOrientation::from(image_header.orientation),
+        row_length: image_header.row_length,
+        data: reader.read_bytes(image_header.data_length as usize)?,
+        decoded_checksum: None
+    };
+    reader.realign()?;
+    let mut image = ImageRef {
+        flags: image_header.flags,
+        width: image_header.width,
+        height: image_header.height,
+        channels: image_header.channels,
+        pixel_format,
+        image_type: ImageType::from(image_header.image_type),
+        child_images: image_header.child_images,
+        texture_flags: image_header.texture_flags,
+        metadata,
+        data: Cow::Borrowed(&[]),
+        orientation: budget.options().target_orientation(),
+        encoded: Some(encoded)
+    };
+    if budget.options().decode_images {
+        let encoded = image.encoded.as_mut().unwrap();
+        image.data = decode_image(image.width, image.height, image.channels, pixel_format, encoded.clone(), budget)?;
+        encoded.decoded_checksum = Some(pixel_checksum(&image.data));
+    }
+    Ok(image)
+}
 
-        let prev_length = reader.bytes_read();
-        let mut variants = Vec::with_capacity(font_header.variant_count as usize);
-        for _ in 0..font_header.variant_count {
-            let variant_header = reader.read_struct::<FontVariantHeader>()?;
-            variants.push(FontVariant {
-                flags: variant_header.flags,
-                weight: variant_header.weight,
-                codepoint_type: CodepointType::from(variant_header.codepoint_type),
-                image_type: ImageType::from(variant_header.image_type),
-                fallback_variant: variant_header.fallback_variant,
-                fallback_glyph: variant_header.fallback_glyph,
-                metrics: bytemuck::cast_slice(&variant_header.metrics[..8])[0],
-                name: reader.read_string(variant_header.name_length as usize)??,
-                metadata: reader.read_string(variant_header.metadata_length as usize)??,
-                glyphs: reader.read_struct_array(variant_header.glyph_count as usize)?,
-                kern_pairs: reader.read_struct_array(variant_header.kern_pair_count as usize)?
-            });
+fn read_appendix<'a, S: Source<'a>>(reader: &mut S, real: RealType, section_length: &SectionLength, budget: &mut Budget) -> Result<AppendixRef<'a>, Error> {
+    let appendix_header = reader.read_struct::<AppendixHeader>(real)?;
+    section_length.ensure_fits(reader.bytes_read(), appendix_header.payload_length())?;
+    charge(reader, budget, appendix_header.metadata_length as usize)?;
+    charge(reader, budget, appendix_header.data_length as usize)?;
+    let appendix = AppendixRef {
+        metadata: reader.read_string(appendix_header.metadata_length as usize)??,
+        data: reader.read_bytes(appendix_header.data_length as usize)?,
+    };
+    reader.realign()?;
+    Ok(appendix)
+}
+
+fn read_font_header<'a, S: Source<'a>>(reader: &mut S) -> Result<(ArteryFontHeader, RealType), Error> {
+    let font_header = reader.read_struct::<ArteryFontHeader>(RealType::native())?;
+    ensure!(font_header.tag == *ARTERY_FONT_HEADER_TAG, ErrorKind::BadMagic);
+    ensure!(font_header.magic_no == ARTERY_FONT_HEADER_MAGIC_NO, ErrorKind::BadMagic);
+    match RealType::from_type_code(font_header.real_type) {
+        Some(real) => Ok((font_header, real)),
+        None => fail!(ErrorKind::UnsupportedRealType(font_header.real_type))
+    }
+}
+
+/// Checks the section lengths declared in the header against the limits of `options`.
+fn ensure_section_lengths(font_header: &ArteryFontHeader, section: &mut Section, options: &ReadOptions) -> Result<(), Error> {
+    let sections = [
+        (Section::Metadata, font_header.metadata_length),
+        (Section::Variants, font_header.variants_length),
+        (Section::Images, font_header.images_length),
+        (Section::Appendices, font_header.appendices_length)
+    ];
+    for (declared, length) in sections {
+        *section = declared;
+        options.ensure_section_length(length)?;
+    }
+    Ok(())
+}
+
+fn probe<'a, S: Source<'a>>(mut reader: S, options: &ReadOptions, skip: fn(&mut S, usize) -> IoResult<()>) -> Result<FontIndex, Error> {
+    let mut section = Section::Header;
+    probe_sections(&mut reader, &mut section, options, skip)
+        .map_err(|err| err.at(section, reader.bytes_read()))
+}
+
+fn probe_sections<'a, S: Source<'a>>(reader: &mut S, section: &mut Section, options: &ReadOptions, skip: fn(&mut S, usize) -> IoResult<()>) -> Result<FontIndex, Error> {
+
+    let (font_header, real) = read_font_header(reader)?;
+    ensure_section_lengths(&font_header, section, options)?;
+
+    *section = Section::Metadata;
+    skip(reader, string_length(font_header.metadata_length as usize))?;
+
+    let variants_length = SectionLength::new(reader.bytes_read(), font_header.variants_length as usize);
+    let variant_count = font_header.variant_count as usize;
+    *section = Section::Variants;
+    variants_length.ensure_fits(reader.bytes_read(), variant_count.saturating_mul(FontVariantHeader::file_size(real)))?;
+    let mut variants = Vec::with_capacity(variant_count);
+    let mut variant_ranges = Vec::with_capacity(variant_count);
+    for i in 0..variant_count {
+        *section = Section::Variant(i);
+        let start = reader.bytes_read();
+        let variant_header = reader.read_struct::<FontVariantHeader>(real)?;
+        variants_length.ensure_fits(reader.bytes_read(), variant_header.payload_length(real))?;
+        variants.push(FontVariantSummary {
+            flags: variant_header.flags,
+            weight: variant_header.weight,
+            codepoint_type: CodepointType::from(variant_header.codepoint_type),
+            image_type: ImageType::from(variant_header.image_type),
+            fallback_variant: variant_header.fallback_variant,
+            fallback_glyph: variant_header.fallback_glyph,
+            metrics: bytemuck::cast_slice(&variant_header.metrics[..8])[0],
+            name: reader.read_string(variant_header.name_length as usize)??.into_owned(),
+            glyph_count: variant_header.glyph_count,
+            kern_pair_count: variant_header.kern_pair_count
+        });
+        skip(reader, variant_header.payload_length(real) - string_length(variant_header.name_length as usize))?;
+        variant_ranges.push(start..reader.bytes_read());
+    }
+    *section = Section::Variants;
+    variants_length.ensure_complete(reader.bytes_read())?;
+
+    let images_length = SectionLength::new(reader.bytes_read(), font_header.images_length as usize);
+    let image_count = font_header.image_count as usize;
+    *section = Section::Images;
+    images_length.ensure_fits(reader.bytes_read(), image_count.saturating_mul(ImageHeader::file_size(real)))?;
+    let mut images = Vec::with_capacity(image_count);
+    let mut image_ranges = Vec::with_capacity(image_count);
+    for i in 0..image_count {
+        *section = Section::Image(i);
+        let start = reader.bytes_read();
+        let image_header = reader.read_struct::<ImageHeader>(real)?;
+        images_length.ensure_fits(reader.bytes_read(), image_header.payload_length())?;
+        images.push(ImageSummary {
+            flags: image_header.flags,
+            encoding: ImageEncoding::from(image_header.encoding),
+            width: image_header.width,
+            height: image_header.height,
+            channels: image_header.channels,
+            pixel_format: PixelFormat::from(image_header.pixel_format),
+            image_type: ImageType::from(image_header.image_type),
+            orientation: ImageOrientation::from(image_header.orientation),
+            child_images: image_header.child_images,
+            texture_flags: image_header.texture_flags,
+            data_length: image_header.data_length
+        });
+        skip(reader, image_header.payload_length())?;
+        image_ranges.push(start..reader.bytes_read());
+    }
+    *section = Section::Images;
+    images_length.ensure_complete(reader.bytes_read())?;
+
+    let appendices_length = SectionLength::new(reader.bytes_read(), font_header.appendices_length as usize);
+    let appendix_count = font_header.appendix_count as usize;
+    *section = Section::Appendices;
+    appendices_length.ensure_fits(reader.bytes_read(), appendix_count.saturating_mul(AppendixHeader::file_size(real)))?;
+    let mut appendix_ranges = Vec::with_capacity(appendix_count);
+    for i in 0..appendix_count {
+        *section = Section::Appendix(i);
+        let start = reader.bytes_read();
+        let appendix_header = reader.read_struct::<AppendixHeader>(real)?;
+        appendices_length.ensure_fits(reader.bytes_read(), appendix_header.payload_length())?;
+        skip(reader, appendix_header.payload_length())?;
+        appendix_ranges.push(start..reader.bytes_read());
+    }
+    *section = Section::Appendices;
+    appendices_length.ensure_complete(reader.bytes_read())?;
+
+    Ok(FontIndex {
+        real,
+        summary: ArteryFontSummary {
+            variants,
+            images,
+            appendix_count: font_header.appendix_count
+        },
+        variants: variant_ranges,
+        images: image_ranges,
+        appendices: appendix_ranges
+    })
+}
+
+/// The headers of a font together with the byte ranges of its variants, images and appendices.
+struct FontIndex {
+    real: RealType,
+    summary: ArteryFontSummary,
+    variants: Vec<Range<usize>>,
+    images: Vec<Range<usize>>,
+    appendices: Vec<Range<usize>>
+}
+
+/// The length the header declares for a section, used to reject bogus lengths inside the section before reading them.
+struct SectionLength {
+    start: usize,
+    declared: usize
+}
+
+impl SectionLength {
+
+    fn new(start: usize, declared: usize) -> Self {
+        Self {
+            start,
+            declared
+        }
+    }
+
+    /// Ensures that the `length` bytes at `position` still belong to the section.
+    fn ensure_fits(&self, position: usize, length: usize) -> Result<(), Error> {
+        let actual = (position - self.start).saturating_add(length);
+        ensure!(actual <= self.declared, ErrorKind::LengthMismatch { expected: self.declared, actual });
+        Ok(())
+    }
+
+    fn ensure_complete(&self, position: usize) -> Result<(), Error> {
+        ensure_length(self.declared, position - self.start)
+    }
+
+}
+
+impl Image {
+
+    /// Decodes the embedded image into [`Image::data`] if that has not happened yet.
+    ///
+    /// The rows are stored in the orientation the font was read with, see [`Image::orientation`].
+    pub fn decode(&mut self) -> Result<&[u8], Error> {
+        self.decode_with(&ReadOptions {
+            orientation: self.orientation,
+            ..Default::default()
+        })
+    }
+
+    /// Like [`Image::decode`], but uses the orientation and allocation limit of `options`.
+    pub fn decode_with(&mut self, options: &ReadOptions) -> Result<&[u8], Error> {
+        if let (true, Some(encoded)) = (self.data.is_empty(), &mut self.encoded) {
+            self.data = decode_image(self.width, self.height, self.channels, self.pixel_format, encoded.as_ref(), &mut Budget::new(options))?.into_owned();
+            self.orientation = options.target_orientation();
+            encoded.decoded_checksum = Some(pixel_checksum(&self.data));
+        }
+        Ok(&self.data)
+    }
+
+}
+
+impl<'a> ImageRef<'a> {
+
+    /// Decodes the embedded image into [`ImageRef::data`] if that has not happened yet.
+    ///
+    /// The rows are stored in the orientation the font was parsed with, see [`ImageRef::orientation`].
+    pub fn decode(&mut self) -> Result<&[u8], Error> {
+        self.decode_with(&ReadOptions {
+            orientation: self.orientation,
+            ..Default::default()
+        })
+    }
+
+    /// Like [`ImageRef::decode`], but uses the orientation and allocation limit of `options`.
+    pub fn decode_with(&mut self, options: &ReadOptions) -> Result<&[u8], Error> {
+        if let (true, Some(encoded)) = (self.data.is_empty(), &mut self.encoded) {
+            self.data = decode_image(self.width, self.height, self.channels, self.pixel_format, encoded.clone(), &mut Budget::new(options))?;
+            self.orientation = options.target_orientation();
+            encoded.decoded_checksum = Some(pixel_checksum(&self.data));
         }
-        ensure!(reader.bytes_read() - prev_length == font_header.variants_length as usize, "variant section longer/shorter than expected");
-
-        let prev_length = reader.bytes_read();
-        let mut images = Vec::with_capacity(font_header.image_count as usize);
-        for _ in 0..font_header.image_count {
-            let image_header = reader.read_struct::<ImageHeader>()?;
-            let encoding = ImageEncoding::from(image_header.encoding);
-            let pixel_format = PixelFormat::from(image_header.pixel_format);
-            let metadata = reader.read_string(image_header.metadata_length as usize)??;
-            let data = match encoding {
-                #[cfg(feature = "png")]
-                ImageEncoding::Png => {
-                    let mut decoder = png::Decoder::new((&mut reader).take(image_header.data_length as u64));
-                    decoder.set_transformations(png::Transformations::EXPAND);
-                    let mut reader = decoder.read_info()?;
+        Ok(&self.data)
+    }
+
+}
+
+fn decode_image<'a>(width: u32, height: u32, channels: u32, pixel_format: PixelFormat, encoded: EncodedImageRef<'a>, budget: &mut Budget) -> Result<Cow<'a, [u8]>, Error> {
+    let image_header = &ImageHeader {
+        flags: 0,
+        encoding: encoded.encoding.into(),
+        width,
+        height,
+        channels,
+        pixel_format: pixel_format.into(),
+        image_type: 0,
+        row_length: encoded.row_length,
+        orientation: encoded.orientation.into(),
+        child_images: 0,
+        texture_flags: 0,
+        reserved: [0; 3],
+        metadata_length: 0,
+        data_length: 0
+    };
+    budget.allocate((width as usize)
+        .saturating_mul(height as usize)
+        .saturating_mul(channels as usize)
+        .saturating_mul(pixel_format.bits()) / 8)?;
+    let (encoding, encoded) = (encoded.encoding, encoded.data);
+    let (data, orientation, row_length) = match encoding {
+        #[cfg(feature = "png")]
+        ImageEncoding::Png => {
+            let mut decoder = png::Decoder::new(&encoded[..]);
+            decoder.set_transformations(png::Transformations::EXPAND);
+            let mut reader = decoder.read_info()?;
+            let (color_type, bit_depth) = reader.output_color_type();
+            ensure_image_matches(image_header, reader.info().width, reader.info().height, color_type.samples(), bit_depth as usize)?;
+            let line_size = reader.output_line_size(width);
+            let buf = match reader.info().interlaced {
+                // Interlaced passes are spread over the whole image, which has already been charged to the budget
+                true => {
                     let mut buf = vec![0u8; reader.output_buffer_size()];
-                    let info = reader.next_frame(&mut buf)?;
-                    ensure!(info.color_type.samples() == image_header.channels as usize, "the channels of the embedded png does not match the image header");
-                    ensure!(info.bit_depth as usize == pixel_format.bits(), "the bit depth of the embedded png does not match the image header");
-                    ensure!(info.width == image_header.width, "the width of the embedded png does not match the image header");
-                    ensure!(info.height == image_header.height, "the hight of the embedded png does not match the image header");
-                    flip_vertically(&mut buf, info.line_size);
+                    reader.next_frame(&mut buf)?;
                     buf
                 },
-                ImageEncoding::RawBinary => {
-                    let mut data = reader.read_struct_array(image_header.data_length as usize)?;
-                    match ImageOrientation::from(image_header.orientation) {
-                        ImageOrientation::BottomUp => {},
-                        ImageOrientation::TopDown => flip_vertically(&mut data, image_header.row_length as usize),
-                        ImageOrientation::Unknown => fail!("Unknown orientation")
+                false => {
+                    let mut buf = Vec::new();
+                    while let Some(row) = reader.next_row()? {
+                        buf.extend_from_slice(row.data());
                     }
-                    data
+                    buf
                 }
-                ImageEncoding::UnknownEncoding => fail!("Unknown encoding"),
-                _ => fail!("Encoding {:?} not supported or enabled", encoding)
             };
-            reader.realign()?;
-            images.push(Image {
-                flags: image_header.flags,
-                width: image_header.width,
-                height: image_header.height,
-                channels: image_header.channels,
-                pixel_format,
-                image_type: ImageType::from(image_header.image_type),
-                child_images: image_header.child_images,
-                texture_flags: image_header.texture_flags,
-                metadata,
-                data
-            });
-        }
-        ensure!(reader.bytes_read() - prev_length == font_header.images_length as usize, "image section longer/shorter than expected");
-
-        let prev_length = reader.bytes_read();
-        let mut appendices = Vec::with_capacity(font_header.appendix_count as usize);
-        for _ in 0..font_header.appendix_count {
-            let appendix_header = reader.read_struct::<AppendixHeader>()?;
-            appendices.push(Appendix {
-                metadata: reader.read_string(appendix_header.metadata_length as usize)??,
-                data: reader.read_struct_array(appendix_header.data_length as usize)?,
-            });
-            reader.realign()?;
+            ensure!(buf.len() == line_size * height as usize, ErrorKind::InvalidImage { encoding, reason: "truncated pixel data" });
+            (Cow::Owned(buf), ImageOrientation::TopDown, line_size)
+        },
+        #[cfg(feature = "bmp")]
+        ImageEncoding::Bmp => bottom_up(bmp::decode(&encoded, image_header)?, height),
+        #[cfg(feature = "tga")]
+        ImageEncoding::Tga => bottom_up(tga::decode(&encoded, image_header)?, height),
+        #[cfg(feature = "tiff")]
+        ImageEncoding::Tiff => bottom_up(tiff::decode(&encoded, image_header)?, height),
+        ImageEncoding::RawBinary => match ImageOrientation::from(image_header.orientation) {
+            ImageOrientation::Unknown => fail!(ErrorKind::UnknownOrientation(image_header.orientation)),
+            orientation => {
+                let row_length = image_header.row_length as usize;
+                ensure!(row_length == 0 || encoded.len() % row_length == 0, ErrorKind::InvalidImage { encoding, reason: "incomplete row" });
+                match pixel_format == PixelFormat::Float32 && SWAP_BYTES {
+                    true => {
+                        let mut data = encoded.into_owned();
+                        swap_samples(&mut data);
+                        (Cow::Owned(data), orientation, row_length)
+                    },
+                    false => (encoded, orientation, row_length)
+                }
+            }
+        },
+        #[allow(unreachable_patterns)]
+        _ => fail!(ErrorKind::UnsupportedEncoding(encoding))
+    };
+    let target = budget.options().target_orientation();
+    Ok(match orientation == target || row_length == 0 {
+        true => data,
+        false => {
+            let mut data = data.into_owned();
+            flip_vertically(&mut data, row_length);
+            Cow::Owned(data)
         }
-        ensure!(reader.bytes_read() - prev_length == font_header.appendix_count as usize, "appendix section longer/shorter than expected");
+    })
+}
 
-        let footer = reader.read_struct::<ArteryFontFooter>()?;
-        ensure!(footer.magic_no == ARTERY_FONT_FOOTER_MAGIC_NO, "bad footer");
+/// Pairs the tightly packed, bottom-up output of the built-in decoders with its orientation and row length.
+#[cfg(any(feature = "bmp", feature = "tga", feature = "tiff"))]
+fn bottom_up(data: Vec<u8>, height: u32) -> (Cow<'static, [u8]>, ImageOrientation, usize) {
+    let row_length = data.len() / height as usize;
+    (Cow::Owned(data), ImageOrientation::BottomUp, row_length)
+}
 
-        #[cfg(not(feature = "no-checksum"))]
-        {
-            let checksum = reader.checksum();
-            let footer_checksum = reader.read_struct::<u32>()?;
-            ensure!(checksum == footer_checksum, "bad checksum");
+/// Returns the encoding, orientation, row length and bytes an image is written with.
+fn stored_image(image: &Image) -> (ImageEncoding, ImageOrientation, usize, Cow<'_, [u8]>) {
+    match &image.encoded {
+        Some(encoded) if image.data.is_empty() || is_decoded(image, encoded) => (encoded.encoding, encoded.orientation, encoded.row_length as usize, Cow::Borrowed(&encoded.data)),
+        _ => {
+            let row_length = match image.height {
+                0 => 0,
+                height => image.data.len() / height as usize
+            };
+            let orientation = match image.orientation {
+                ImageOrientation::TopDown => ImageOrientation::TopDown,
+                _ => ImageOrientation::BottomUp
+            };
+            let data = match image.pixel_format == PixelFormat::Float32 && SWAP_BYTES {
+                true => {
+                    let mut data = image.data.clone();
+                    swap_samples(&mut data);
+                    Cow::Owned(data)
+                },
+                false => Cow::Borrowed(&image.data[..])
+            };
+            (ImageEncoding::RawBinary, orientation, row_length, data)
         }
-        #[cfg(feature = "no-checksum")]
-        let _ = reader.read_struct::<u32>()?;
+    }
+}
 
-        ensure!(reader.bytes_read() == footer.total_length as usize, "total file size longer/shorter than expected");
+/// Whether [`Image::data`] is still the decoded form of `encoded`, so it has not been edited since decoding.
+fn is_decoded(image: &Image, encoded: &EncodedImage) -> bool {
+    encoded.decoded_checksum == Some(pixel_checksum(&image.data))
+}
 
-        Ok(Self {
-            metadata_format,
-            variants,
-            images,
-            appendices
-        })
-    }
+fn pixel_checksum(data: &[u8]) -> u32 {
+    let mut hasher = crc32::Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
 
-    #[cfg(not(target_endian = "little"))]
-    pub fn read<R: Read>(reader: R) -> Result<!, Error> {
-        fail!("big endian is not supported")
-    }
+fn to_u32(len: usize) -> Result<u32, Error> {
+    u32::try_from(len).map_err(|_| ErrorKind::TooLarge(len).into())
+}
 
+fn ensure_length(expected: usize, actual: usize) -> Result<(), Error> {
+    ensure!(expected == actual, ErrorKind::LengthMismatch { expected, actual });
+    Ok(())
+}
+
+/// Checks the dimensions and format of a decoded image against its image header.
+#[cfg(any(feature = "png", feature = "bmp", feature = "tga", feature = "tiff"))]
+fn ensure_image_matches(image_header: &ImageHeader, width: u32, height: u32, channels: usize, bits: usize) -> Result<(), Error> {
+    let checks = [
+        (ImageProperty::Width, image_header.width, width),
+        (ImageProperty::Height, image_header.height, height),
+        (ImageProperty::Channels, image_header.channels, channels as u32),
+        (ImageProperty::BitDepth, PixelFormat::from(image_header.pixel_format).bits() as u32, bits as u32)
+    ];
+    for (property, expected, actual) in checks {
+        ensure!(expected == actual, ErrorKind::ImageMismatch { property, expected, actual });
+    }
+    Ok(())
 }
 
 fn flip_vertically(data: &mut [u8], bytes_per_row: usize) {
@@ -170,3 +790,29 @@ fn flip_vertically(data: &mut [u8], bytes_per_row: usize) {
     }
 }
 
+/// Reverses the byte order of every 32 bit float sample.
+fn swap_samples(data: &mut [u8]) {
+    for sample in data.chunks_exact_mut(4) {
+        sample.reverse();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn probe_stream_matches_probe() {
+        let data = &include_bytes!("../example/data/test.arfont")[..];
+        let seeking = probe(ReadWrapper::new(Cursor::new(data), false), &ReadOptions::default(), ReadWrapper::seek_forward).unwrap();
+        let streaming = probe(ReadWrapper::new(data, false), &ReadOptions::default(), |reader, len| reader.skip(len)).unwrap();
+        assert_eq!(seeking.real, streaming.real);
+        assert_eq!(seeking.variants, streaming.variants);
+        assert_eq!(seeking.images, streaming.images);
+        assert_eq!(seeking.appendices, streaming.appendices);
+        assert_eq!(format!("{:?}", seeking.summary), format!("{:?}", streaming.summary));
+        assert_eq!(format!("{:?}", ArteryFont::probe(Cursor::new(data)).unwrap()), format!("{:?}", ArteryFont::probe_stream(data).unwrap()));
+    }
+}
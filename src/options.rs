@@ -0,0 +1,100 @@
+use crate::enums::ImageOrientation;
+use crate::error::{Error, ErrorKind};
+
+/// Settings for [`ArteryFont::read_with`](crate::ArteryFont::read_with) and [`ArteryFontRef::parse_with`](crate::ArteryFontRef::parse_with).
+///
+/// ```
+/// use artery_font::{ImageOrientation, ReadOptions};
+///
+/// let options = ReadOptions {
+///     orientation: ImageOrientation::TopDown,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadOptions {
+    /// Whether the checksum in the footer is verified. Enabled unless the `no-checksum` feature is active.
+    pub verify_checksum: bool,
+    /// The row order of the decoded images. `TopDown` matches most graphics APIs, `BottomUp` matches OpenGL.
+    /// `Unknown` is treated like `BottomUp`.
+    pub orientation: ImageOrientation,
+    /// Whether the embedded images are decoded while reading. See [`ArteryFont::read_lazy`](crate::ArteryFont::read_lazy).
+    pub decode_images: bool,
+    /// The largest buffer in bytes that may be allocated for a single string, table, image or appendix. 256 MiB by default.
+    pub max_allocation: usize,
+    /// The largest length in bytes the header may declare for the metadata, variant, image or appendix section. 1 GiB by default.
+    pub max_section_length: usize,
+    /// The largest number of bytes that may be allocated in total, including decoded images. 1 GiB by default.
+    ///
+    /// Data that [`ArteryFontRef::parse_with`](crate::ArteryFontRef::parse_with) borrows instead of copying does not count
+    /// towards either allocation limit.
+    pub max_total_allocation: usize
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            verify_checksum: !cfg!(feature = "no-checksum"),
+            orientation: ImageOrientation::BottomUp,
+            decode_images: true,
+            max_allocation: 256 << 20,
+            max_section_length: 1 << 30,
+            max_total_allocation: 1 << 30
+        }
+    }
+}
+
+impl ReadOptions {
+
+    /// The row order decoded images are stored in, which is [`ReadOptions::orientation`] with `Unknown` replaced by `BottomUp`.
+    pub(crate) fn target_orientation(&self) -> ImageOrientation {
+        match self.orientation {
+            ImageOrientation::TopDown => ImageOrientation::TopDown,
+            _ => ImageOrientation::BottomUp
+        }
+    }
+
+    pub(crate) fn ensure_section_length(&self, length: u32) -> Result<(), Error> {
+        let requested = length as usize;
+        ensure!(requested <= self.max_section_length, ErrorKind::AllocationLimit { requested, limit: self.max_section_length });
+        Ok(())
+    }
+
+}
+
+/// Keeps track of the memory allocated while reading a font.
+pub(crate) struct Budget {
+    options: ReadOptions,
+    used: usize
+}
+
+impl Budget {
+
+    pub fn new(options: &ReadOptions) -> Self {
+        Self {
+            options: *options,
+            used: 0
+        }
+    }
+
+    pub fn options(&self) -> &ReadOptions {
+        &self.options
+    }
+
+    /// Checks a single allocation of `requested` bytes without accounting for it.
+    pub fn check(&self, requested: usize) -> Result<(), Error> {
+        let limit = self.options.max_allocation;
+        ensure!(requested <= limit, ErrorKind::AllocationLimit { requested, limit });
+        Ok(())
+    }
+
+    /// Accounts for an allocation of `requested` bytes, failing if it exceeds either the single or the total limit.
+    pub fn allocate(&mut self, requested: usize) -> Result<(), Error> {
+        self.check(requested)?;
+        self.used = self.used.saturating_add(requested);
+        let limit = self.options.max_total_allocation;
+        ensure!(self.used <= limit, ErrorKind::AllocationLimit { requested: self.used, limit });
+        Ok(())
+    }
+
+}
@@ -0,0 +1,274 @@
+use crate::crc32::Hasher;
+use crate::header::*;
+use crate::options::Budget;
+use crate::util::{string_length, SliceReader, Source};
+use crate::{ensure_length, ensure_section_lengths, read_appendix, read_font_header, read_image, read_metadata, read_variant, SectionLength};
+use crate::{Appendix, Error, ErrorKind, FontVariant, Image, MetadataFormat, ReadOptions, Section};
+
+/// An event produced by [`ArteryFontParser::poll`].
+#[derive(Debug, Clone)]
+pub enum ParseEvent {
+    /// The header and the font metadata have been parsed.
+    Header {
+        metadata_format: MetadataFormat,
+        variant_count: u32,
+        image_count: u32,
+        appendix_count: u32
+    },
+    Variant(usize, FontVariant),
+    Image(usize, Image),
+    Appendix(usize, Appendix),
+    /// The footer has been parsed and the checksum and total length are verified. No further events follow.
+    Done
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Header,
+    Variants(usize),
+    Images(usize),
+    Appendices(usize),
+    Footer,
+    Done
+}
+
+/// A push based parser that does not perform any I/O itself.
+///
+/// The font data is passed in chunks of any size using [`ArteryFontParser::feed`].
+/// [`ArteryFontParser::poll`] then returns the parts of the font that are complete, or `None` if more data is needed.
+/// The same length and checksum validation as [`ArteryFont::read`](crate::ArteryFont::read) is performed.
+///
+/// ```
+/// use artery_font::{ArteryFontParser, ParseEvent, ReadOptions};
+///
+/// # let data = include_bytes!("../example/data/test.arfont");
+/// let mut parser = ArteryFontParser::with_options(ReadOptions {
+///     decode_images: false,
+///     ..Default::default()
+/// });
+/// let mut variants = Vec::new();
+/// for chunk in data.chunks(1024) {
+///     parser.feed(chunk);
+///     while let Some(event) = parser.poll()? {
+///         if let ParseEvent::Variant(_, variant) = event {
+///             variants.push(variant);
+///         }
+///     }
+/// }
+/// assert!(parser.is_done());
+/// # Ok::<(), artery_font::Error>(())
+/// ```
+pub struct ArteryFontParser {
+    buffer: Vec<u8>,
+    offset: usize,
+    checksum: Option<Hasher>,
+    budget: Budget,
+    state: State,
+    font_header: ArteryFontHeader,
+    real: RealType,
+    section_length: SectionLength
+}
+
+impl Default for ArteryFontParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArteryFontParser {
+
+    pub fn new() -> Self {
+        Self::with_options(ReadOptions::default())
+    }
+
+    pub fn with_options(options: ReadOptions) -> Self {
+        Self {
+            buffer: Vec::new(),
+            offset: 0,
+            checksum: options.verify_checksum.then(Hasher::new),
+            budget: Budget::new(&options),
+            state: State::Header,
+            font_header: bytemuck::Zeroable::zeroed(),
+            real: RealType::native(),
+            section_length: SectionLength::new(0, 0)
+        }
+    }
+
+    /// Appends the next chunk of the font file.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Parses the next complete part of the font. Returns `None` if more data has to be fed first or if parsing is done.
+    pub fn poll(&mut self) -> Result<Option<ParseEvent>, Error> {
+        let mut section = Section::Header;
+        self.poll_event(&mut section)
+            .map_err(|err| err.at(section, self.offset))
+    }
+
+    /// Whether the whole font has been parsed.
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    fn poll_event(&mut self, section: &mut Section) -> Result<Option<ParseEvent>, Error> {
+        loop {
+            let real = self.real;
+            match self.state {
+                State::Header => {
+                    let header_length = ArteryFontHeader::file_size(RealType::native());
+                    if self.buffer.len() < header_length {
+                        return Ok(None);
+                    }
+                    let mut reader = SliceReader::with_offset(&self.buffer, self.offset);
+                    let (font_header, real) = read_font_header(&mut reader).map_err(|err| err.at(*section, reader.bytes_read()))?;
+                    ensure_section_lengths(&font_header, section, self.budget.options())?;
+                    *section = Section::Metadata;
+                    let length = header_length + string_length(font_header.metadata_length as usize);
+                    if self.buffer.len() < length {
+                        return Ok(None);
+                    }
+                    let mut reader = SliceReader::with_offset(&self.buffer[..length], self.offset);
+                    reader.skip(header_length)?;
+                    let metadata_format = read_metadata(&mut reader, &font_header, &mut self.budget)
+                        .map_err(|err| err.at(*section, reader.bytes_read()))?
+                        .into_owned();
+                    self.consume(length);
+                    self.font_header = font_header;
+                    self.real = real;
+                    *section = Section::Variants;
+                    self.begin_section(State::Variants(0))?;
+                    return Ok(Some(ParseEvent::Header {
+                        metadata_format,
+                        variant_count: font_header.variant_count,
+                        image_count: font_header.image_count,
+                        appendix_count: font_header.appendix_count
+                    }));
+                },
+                State::Variants(i) if i < self.font_header.variant_count as usize => {
+                    *section = Section::Variant(i);
+                    let length = match self.peek::<FontVariantHeader>()? {
+                        Some(header) => FontVariantHeader::file_size(real).saturating_add(header.payload_length(real)),
+                        None => return Ok(None)
+                    };
+                    self.section_length.ensure_fits(self.offset, length)?;
+                    if self.buffer.len() < length {
+                        return Ok(None);
+                    }
+                    let mut reader = SliceReader::with_offset(&self.buffer[..length], self.offset);
+                    let variant = read_variant(&mut reader, real, &self.section_length, &mut self.budget)
+                        .map_err(|err| err.at(*section, reader.bytes_read()))?
+                        .into_owned();
+                    self.consume(length);
+                    self.state = State::Variants(i + 1);
+                    return Ok(Some(ParseEvent::Variant(i, variant)));
+                },
+                State::Images(i) if i < self.font_header.image_count as usize => {
+                    *section = Section::Image(i);
+                    let length = match self.peek::<ImageHeader>()? {
+                        Some(header) => ImageHeader::file_size(real) + header.payload_length(),
+                        None => return Ok(None)
+                    };
+                    self.section_length.ensure_fits(self.offset, length)?;
+                    if self.buffer.len() < length {
+                        return Ok(None);
+                    }
+                    let mut reader = SliceReader::with_offset(&self.buffer[..length], self.offset);
+                    let image = read_image(&mut reader, real, &self.section_length, &mut self.budget)
+                        .map_err(|err| err.at(*section, reader.bytes_read()))?
+                        .into_owned();
+                    self.consume(length);
+                    self.state = State::Images(i + 1);
+                    return Ok(Some(ParseEvent::Image(i, image)));
+                },
+                State::Appendices(i) if i < self.font_header.appendix_count as usize => {
+                    *section = Section::Appendix(i);
+                    let length = match self.peek::<AppendixHeader>()? {
+                        Some(header) => AppendixHeader::file_size(real) + header.payload_length(),
+                        None => return Ok(None)
+                    };
+                    self.section_length.ensure_fits(self.offset, length)?;
+                    if self.buffer.len() < length {
+                        return Ok(None);
+                    }
+                    let mut reader = SliceReader::with_offset(&self.buffer[..length], self.offset);
+                    let appendix = read_appendix(&mut reader, real, &self.section_length, &mut self.budget)
+                        .map_err(|err| err.at(*section, reader.bytes_read()))?
+                        .into_owned();
+                    self.consume(length);
+                    self.state = State::Appendices(i + 1);
+                    return Ok(Some(ParseEvent::Appendix(i, appendix)));
+                },
+                State::Variants(_) => {
+                    *section = Section::Variants;
+                    self.section_length.ensure_complete(self.offset)?;
+                    *section = Section::Images;
+                    self.begin_section(State::Images(0))?;
+                },
+                State::Images(_) => {
+                    *section = Section::Images;
+                    self.section_length.ensure_complete(self.offset)?;
+                    *section = Section::Appendices;
+                    self.begin_section(State::Appendices(0))?;
+                },
+                State::Appendices(_) => {
+                    *section = Section::Appendices;
+                    self.section_length.ensure_complete(self.offset)?;
+                    self.state = State::Footer;
+                },
+                State::Footer => {
+                    *section = Section::Footer;
+                    let footer_length = ArteryFontFooter::file_size(real);
+                    if self.buffer.len() < footer_length + u32::file_size(real) {
+                        return Ok(None);
+                    }
+                    let mut reader = SliceReader::with_offset(&self.buffer, self.offset);
+                    let footer = reader.read_struct::<ArteryFontFooter>(real)?;
+                    let footer_checksum = reader.read_struct::<u32>(real)?;
+                    self.consume(footer_length);
+                    ensure!(footer.magic_no == ARTERY_FONT_FOOTER_MAGIC_NO, ErrorKind::BadMagic);
+                    let checksum = self.checksum.clone().map(Hasher::finalize);
+                    self.consume(u32::file_size(real));
+                    if let Some(checksum) = checksum {
+                        ensure!(checksum == footer_checksum, ErrorKind::ChecksumMismatch { expected: footer_checksum, actual: checksum });
+                    }
+                    ensure_length(footer.total_length as usize, self.offset)?;
+                    self.state = State::Done;
+                    return Ok(Some(ParseEvent::Done));
+                },
+                State::Done => return Ok(None)
+            }
+        }
+    }
+
+    /// Reads the header of the next item without consuming it.
+    fn peek<S: Layout>(&self) -> Result<Option<S>, Error> {
+        if self.buffer.len() < S::file_size(self.real) {
+            return Ok(None);
+        }
+        Ok(Some(SliceReader::with_offset(&self.buffer, self.offset).read_struct(self.real)?))
+    }
+
+    /// Starts the variant, image or appendix section and checks that its item headers fit into its declared length.
+    fn begin_section(&mut self, state: State) -> Result<(), Error> {
+        let header = &self.font_header;
+        let (declared, count, header_length) = match state {
+            State::Variants(_) => (header.variants_length, header.variant_count, FontVariantHeader::file_size(self.real)),
+            State::Images(_) => (header.images_length, header.image_count, ImageHeader::file_size(self.real)),
+            _ => (header.appendices_length, header.appendix_count, AppendixHeader::file_size(self.real))
+        };
+        self.section_length = SectionLength::new(self.offset, declared as usize);
+        self.section_length.ensure_fits(self.offset, (count as usize).saturating_mul(header_length))?;
+        self.state = state;
+        Ok(())
+    }
+
+    fn consume(&mut self, length: usize) {
+        if let Some(checksum) = &mut self.checksum {
+            checksum.update(&self.buffer[..length]);
+        }
+        self.buffer.drain(..length);
+        self.offset += length;
+    }
+
+}
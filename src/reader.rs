@@ -0,0 +1,101 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use crate::header::RealType;
+use crate::options::Budget;
+use crate::util::{ReadWrapper, Source};
+use crate::{probe, read_appendix, read_image, read_variant, FontIndex, SectionLength};
+use crate::{Appendix, ArteryFontSummary, Error, FontVariant, Image, ReadOptions, Section};
+
+/// Loads individual variants, images and appendices of a seekable font file on demand.
+///
+/// Creating the reader only reads the headers, like [`ArteryFont::probe`](crate::ArteryFont::probe), and remembers where each item starts.
+/// The checksum is never verified because most of the file is not read.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use artery_font::ArteryFontReader;
+///
+/// let mut reader = ArteryFontReader::new(File::open("font.arfont")?)?;
+/// let bold = reader.variant_by_name("Bold")?;
+/// let atlas = reader.image(0)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ArteryFontReader<R> {
+    inner: R,
+    base: u64,
+    options: ReadOptions,
+    index: FontIndex
+}
+
+impl<R: Read + Seek> ArteryFontReader<R> {
+
+    pub fn new(reader: R) -> Result<Self, Error> {
+        Self::with_options(reader, ReadOptions::default())
+    }
+
+    /// Creates a reader that loads items using the given [`ReadOptions`]. `verify_checksum` is ignored.
+    pub fn with_options(mut reader: R, options: ReadOptions) -> Result<Self, Error> {
+        let base = reader.stream_position()?;
+        let index = probe(ReadWrapper::new(&mut reader, false), &options, ReadWrapper::seek_forward)?;
+        Ok(Self {
+            inner: reader,
+            base,
+            options,
+            index
+        })
+    }
+
+    /// The headers of all variants and images.
+    pub fn summary(&self) -> &ArteryFontSummary {
+        &self.index.summary
+    }
+
+    /// Loads the variant at `index`. Returns `None` if there is no such variant.
+    pub fn variant(&mut self, index: usize) -> Result<Option<FontVariant>, Error> {
+        match self.index.variants.get(index).cloned() {
+            Some(range) => self.load(Section::Variant(index), range, read_variant).map(|variant| Some(variant.into_owned())),
+            None => Ok(None)
+        }
+    }
+
+    /// Loads the first variant named `name`. Returns `None` if there is no such variant.
+    pub fn variant_by_name(&mut self, name: &str) -> Result<Option<FontVariant>, Error> {
+        match self.index.summary.variants.iter().position(|variant| variant.name == name) {
+            Some(index) => self.variant(index),
+            None => Ok(None)
+        }
+    }
+
+    /// Loads the image at `index`. Returns `None` if there is no such image.
+    pub fn image(&mut self, index: usize) -> Result<Option<Image>, Error> {
+        match self.index.images.get(index).cloned() {
+            Some(range) => self.load(Section::Image(index), range, read_image).map(|image| Some(image.into_owned())),
+            None => Ok(None)
+        }
+    }
+
+    /// Loads the appendix at `index`. Returns `None` if there is no such appendix.
+    pub fn appendix(&mut self, index: usize) -> Result<Option<Appendix>, Error> {
+        match self.index.appendices.get(index).cloned() {
+            Some(range) => self.load(Section::Appendix(index), range, read_appendix).map(|appendix| Some(appendix.into_owned())),
+            None => Ok(None)
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn load<'b, T>(&'b mut self, section: Section, range: Range<usize>,
+                   read: fn(&mut ReadWrapper<&'b mut R>, RealType, &SectionLength, &mut Budget) -> Result<T, Error>) -> Result<T, Error> {
+        self.inner
+            .seek(SeekFrom::Start(self.base + range.start as u64))
+            .map_err(|err| Error::from(err).at(section, range.start))?;
+        let mut reader = ReadWrapper::new(&mut self.inner, false);
+        let length = SectionLength::new(reader.bytes_read(), range.len());
+        read(&mut reader, self.index.real, &length, &mut Budget::new(&self.options))
+            .and_then(|item| length.ensure_complete(reader.bytes_read()).map(|_| item))
+            .map_err(|err| err.at(section, range.start + reader.bytes_read()))
+    }
+
+}
@@ -1,8 +1,8 @@
 use bytemuck::{Pod, Zeroable};
 use crate::enums::*;
-use crate::header::Real;
+use crate::header::{Field, Layout, Real};
 
-#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Zeroable, Pod)]
 #[repr(C)]
 pub struct Advance {
     pub horizontal: Real,
@@ -43,6 +43,10 @@ pub struct Glyph {
     pub advance: Advance
 }
 
+impl Layout for Glyph {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 2), (Field::Real, 10)];
+}
+
 impl Glyph {
     pub fn is_drawable(self) -> bool{
         !self.plane_bounds.is_empty() && !self.image_bounds.is_empty()
@@ -57,6 +61,10 @@ pub struct KernPair {
     pub advance: Advance
 }
 
+impl Layout for KernPair {
+    const FIELDS: &'static [(Field, usize)] = &[(Field::U32, 2), (Field::Real, 2)];
+}
+
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C)]
 pub struct FontMetric {
@@ -96,7 +104,28 @@ pub struct Image {
     pub child_images: u32,
     pub texture_flags: u32,
     pub metadata: String,
-    pub data: Vec<u8>
+    /// The decoded pixels in the row order given by [`Image::orientation`]. `Float32` samples are in native byte order.
+    /// Empty until [`Image::decode`] is called if the font was read lazily.
+    pub data: Vec<u8>,
+    /// The row order of [`Image::data`], as requested by [`ReadOptions::orientation`](crate::ReadOptions::orientation). Bottom-up by default.
+    pub orientation: ImageOrientation,
+    /// The image as it was stored in the font file. It is kept after decoding.
+    ///
+    /// [`ArteryFont::write`] writes this unchanged as long as [`Image::data`] is empty or still matches it, so edits to the data are not lost.
+    /// Set it to `None` to always write [`Image::data`] instead, or to free the memory of the original bytes.
+    pub encoded: Option<EncodedImage>
+}
+
+/// An embedded image as it is stored in the font file.
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub encoding: ImageEncoding,
+    pub orientation: ImageOrientation,
+    pub row_length: u32,
+    pub data: Vec<u8>,
+    /// The CRC-32 of [`Image::data`] right after the image was decoded. [`ArteryFont::write`] compares it with the current
+    /// data to detect edits without decoding the image again. `None` if the image has not been decoded.
+    pub decoded_checksum: Option<u32>
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,44 @@
+use crate::enums::*;
+use crate::structs::*;
+
+/// The header of a [`FontVariant`] without its metadata, glyphs and kerning pairs.
+#[derive(Debug, Clone)]
+pub struct FontVariantSummary {
+    pub flags: u32,
+    pub weight: u32,
+    pub codepoint_type: CodepointType,
+    pub image_type: ImageType,
+    pub fallback_variant: u32,
+    pub fallback_glyph: u32,
+    pub metrics: FontMetric,
+    pub name: String,
+    pub glyph_count: u32,
+    pub kern_pair_count: u32
+}
+
+/// The header of an [`Image`] without its metadata and pixel data.
+#[derive(Debug, Clone)]
+pub struct ImageSummary {
+    pub flags: u32,
+    pub encoding: ImageEncoding,
+    pub width: u32,
+    pub height: u32,
+    pub channels: u32,
+    pub pixel_format: PixelFormat,
+    pub image_type: ImageType,
+    pub orientation: ImageOrientation,
+    pub child_images: u32,
+    pub texture_flags: u32,
+    /// The size of the encoded image in the file.
+    pub data_length: u32
+}
+
+/// A lightweight description of a font file.
+///
+/// Created using [`ArteryFont::probe`], which only reads the headers and skips everything else.
+#[derive(Debug, Clone)]
+pub struct ArteryFontSummary {
+    pub variants: Vec<FontVariantSummary>,
+    pub images: Vec<ImageSummary>,
+    pub appendix_count: u32
+}
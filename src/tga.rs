@@ -0,0 +1,169 @@
+use crate::enums::ImageEncoding;
+use crate::error::{Error, ErrorKind};
+use crate::{ensure_image_matches, flip_vertically};
+use crate::header::ImageHeader;
+
+const TRUE_COLOR: u8 = 2;
+const GRAYSCALE: u8 = 3;
+const RLE_TRUE_COLOR: u8 = 10;
+const RLE_GRAYSCALE: u8 = 11;
+
+const RIGHT_TO_LEFT: u8 = 0x10;
+const TOP_TO_BOTTOM: u8 = 0x20;
+
+/// Decodes an uncompressed or run-length encoded grayscale, RGB or RGBA TGA into tightly packed, bottom-up rows.
+pub fn decode(data: &[u8], image_header: &ImageHeader) -> Result<Vec<u8>, Error> {
+    ensure!(data.len() >= 18, invalid("bad header"));
+    let id_length = data[0] as usize;
+    let color_map_type = data[1];
+    let image_type = data[2];
+    let color_map_length = u16::from_le_bytes([data[5], data[6]]) as usize;
+    let color_map_bits = data[7] as usize;
+    let width = u16::from_le_bytes([data[12], data[13]]) as usize;
+    let height = u16::from_le_bytes([data[14], data[15]]) as usize;
+    let bits = data[16];
+    let descriptor = data[17];
+
+    let channels = match (image_type, bits) {
+        (GRAYSCALE | RLE_GRAYSCALE, 8) => 1,
+        (GRAYSCALE | RLE_GRAYSCALE, 16) => 2,
+        (TRUE_COLOR | RLE_TRUE_COLOR, 24) => 3,
+        (TRUE_COLOR | RLE_TRUE_COLOR, 32) => 4,
+        _ => fail!(invalid("unsupported image type or bits per pixel"))
+    };
+    ensure!(width > 0 && height > 0, invalid("invalid dimensions"));
+    ensure_image_matches(image_header, width as u32, height as u32, channels, 8)?;
+
+    let color_map_size = match color_map_type {
+        0 => 0,
+        _ => color_map_length * color_map_bits.div_ceil(8)
+    };
+    let offset = 18 + id_length + color_map_size;
+    ensure!(data.len() >= offset, invalid("truncated header"));
+    let mut src = &data[offset..];
+
+    let pixels = width * height;
+    // Grows with the decoded packets instead of trusting the declared dimensions
+    let mut buf = Vec::with_capacity((pixels * channels).min(src.len()));
+    match image_type {
+        TRUE_COLOR | GRAYSCALE => {
+            ensure!(src.len() >= pixels * channels, invalid("truncated pixel data"));
+            for pixel in src[..pixels * channels].chunks_exact(channels) {
+                push_pixel(&mut buf, pixel);
+            }
+        },
+        _ => {
+            while buf.len() < pixels * channels {
+                ensure!(!src.is_empty(), invalid("truncated pixel data"));
+                let packet = src[0];
+                let count = (packet & 0x7f) as usize + 1;
+                let size = match packet & 0x80 {
+                    0 => count * channels,
+                    _ => channels
+                };
+                ensure!(src.len() > size, invalid("truncated pixel data"));
+                let (values, rest) = src[1..].split_at(size);
+                src = rest;
+                match packet & 0x80 {
+                    0 => for pixel in values.chunks_exact(channels) {
+                        push_pixel(&mut buf, pixel);
+                    },
+                    _ => for _ in 0..count {
+                        push_pixel(&mut buf, values);
+                    }
+                }
+            }
+            buf.truncate(pixels * channels);
+        }
+    }
+
+    if descriptor & RIGHT_TO_LEFT != 0 {
+        for row in buf.chunks_exact_mut(width * channels) {
+            let mut pixels = row.chunks_exact_mut(channels);
+            while let (Some(p1), Some(p2)) = (pixels.next(), pixels.next_back()) {
+                p1.swap_with_slice(p2)
+            }
+        }
+    }
+    if descriptor & TOP_TO_BOTTOM != 0 {
+        flip_vertically(&mut buf, width * channels);
+    }
+    Ok(buf)
+}
+
+fn push_pixel(buf: &mut Vec<u8>, pixel: &[u8]) {
+    match pixel.len() {
+        3 => buf.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]),
+        4 => buf.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]),
+        _ => buf.extend_from_slice(pixel)
+    }
+}
+
+fn invalid(reason: &'static str) -> ErrorKind {
+    ErrorKind::InvalidImage { encoding: ImageEncoding::Tga, reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::PixelFormat;
+    use crate::header::test_image_header;
+
+    /// A TGA without an id or color map.
+    fn tga(image_type: u8, width: u16, height: u16, bits: u8, descriptor: u8, pixels: &[u8]) -> Vec<u8> {
+        let mut data = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&[bits, descriptor]);
+        data.extend_from_slice(pixels);
+        data
+    }
+
+    fn reason(result: Result<Vec<u8>, Error>) -> &'static str {
+        match result.map_err(Error::into_kind) {
+            Err(ErrorKind::InvalidImage { encoding: ImageEncoding::Tga, reason }) => reason,
+            other => panic!("expected an invalid image, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn true_color() {
+        let data = tga(TRUE_COLOR, 1, 2, 24, 0, &[3, 2, 1, 6, 5, 4]);
+        let image = decode(&data, &test_image_header(1, 2, 3, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn top_down_right_to_left() {
+        let data = tga(GRAYSCALE, 2, 2, 8, TOP_TO_BOTTOM | RIGHT_TO_LEFT, &[1, 2, 3, 4]);
+        let image = decode(&data, &test_image_header(2, 2, 1, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn run_length_encoded() {
+        // A run of three pixels followed by a single raw pixel
+        let data = tga(RLE_TRUE_COLOR, 2, 2, 32, 0, &[0x82, 3, 2, 1, 9, 0x00, 6, 5, 4, 8]);
+        let image = decode(&data, &test_image_header(2, 2, 4, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [1, 2, 3, 9, 1, 2, 3, 9, 1, 2, 3, 9, 4, 5, 6, 8]);
+    }
+
+    #[test]
+    fn zero_size() {
+        let data = tga(GRAYSCALE, 0, 2, 8, 0, &[]);
+        let result = decode(&data, &test_image_header(0, 2, 1, PixelFormat::Unsigned8));
+        assert_eq!(reason(result), "invalid dimensions");
+    }
+
+    #[test]
+    fn truncated() {
+        let header = test_image_header(2, 2, 4, PixelFormat::Unsigned8);
+        for data in [tga(TRUE_COLOR, 2, 2, 32, 0, &[0; 16]), tga(RLE_TRUE_COLOR, 2, 2, 32, 0, &[0x01, 3, 2, 1, 9, 6, 5, 4, 8, 0x81, 1, 1, 1, 1])] {
+            assert!(decode(&data, &header).is_ok());
+            for len in 0..data.len() {
+                assert!(decode(&data[..len], &header).is_err(), "{} bytes", len);
+            }
+        }
+    }
+
+}
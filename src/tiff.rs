@@ -0,0 +1,298 @@
+use crate::enums::{ImageEncoding, PixelFormat};
+use crate::error::{Error, ErrorKind};
+use crate::{ensure_image_matches, flip_vertically, swap_samples};
+use crate::header::ImageHeader;
+
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const STRIP_OFFSETS: u16 = 273;
+const ORIENTATION: u16 = 274;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const PLANAR_CONFIGURATION: u16 = 284;
+const SAMPLE_FORMAT: u16 = 339;
+
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+
+const NO_COMPRESSION: u32 = 1;
+const PACK_BITS: u32 = 32773;
+
+const SAMPLE_FORMAT_UINT: u32 = 1;
+const SAMPLE_FORMAT_FLOAT: u32 = 3;
+
+const ORIENTATION_TOP_LEFT: u32 = 1;
+const ORIENTATION_BOTTOM_LEFT: u32 = 4;
+
+/// Decodes the first image of a baseline TIFF with 8 bit unsigned or 32 bit float samples into tightly packed, bottom-up rows.
+///
+/// Float samples are stored in native byte order.
+pub fn decode(data: &[u8], image_header: &ImageHeader) -> Result<Vec<u8>, Error> {
+    ensure!(data.len() >= 8, invalid("bad header"));
+    let tiff = Tiff {
+        data,
+        big_endian: match &data[..4] {
+            b"II\x2a\x00" => false,
+            b"MM\x00\x2a" => true,
+            _ => fail!(invalid("bad header"))
+        }
+    };
+
+    let ifd = tiff.u32(4)? as usize;
+    let entries = tiff.u16(ifd)? as usize;
+    let mut width = 0;
+    let mut height = 0;
+    let mut bits = vec![1];
+    let mut compression = NO_COMPRESSION;
+    let mut strip_offsets = Vec::new();
+    let mut orientation = ORIENTATION_TOP_LEFT;
+    let mut samples = 1;
+    let mut rows_per_strip = u32::MAX;
+    let mut strip_byte_counts = Vec::new();
+    let mut planar_configuration = 1;
+    let mut sample_format = vec![SAMPLE_FORMAT_UINT];
+    for i in 0..entries {
+        let entry = ifd + 2 + 12 * i;
+        let values = || tiff.values(entry);
+        match tiff.u16(entry)? {
+            IMAGE_WIDTH => width = first(values()?)?,
+            IMAGE_LENGTH => height = first(values()?)?,
+            BITS_PER_SAMPLE => bits = values()?,
+            COMPRESSION => compression = first(values()?)?,
+            STRIP_OFFSETS => strip_offsets = values()?,
+            ORIENTATION => orientation = first(values()?)?,
+            SAMPLES_PER_PIXEL => samples = first(values()?)?,
+            ROWS_PER_STRIP => rows_per_strip = first(values()?)?,
+            STRIP_BYTE_COUNTS => strip_byte_counts = values()?,
+            PLANAR_CONFIGURATION => planar_configuration = first(values()?)?,
+            SAMPLE_FORMAT => sample_format = values()?,
+            _ => {}
+        }
+    }
+
+    let (width, height, channels) = (width as usize, height as usize, samples as usize);
+    ensure!(width > 0 && height > 0, invalid("invalid dimensions"));
+    ensure!(channels > 0, invalid("invalid samples per pixel"));
+    ensure!(planar_configuration == 1 || channels == 1, invalid("unsupported planar sample storage"));
+    ensure!(!bits.is_empty() && !sample_format.is_empty(), invalid("empty field"));
+    ensure!(bits.iter().all(|&b| b == bits[0]), invalid("mixed bits per sample"));
+    ensure!(sample_format.iter().all(|&f| f == sample_format[0]), invalid("mixed sample formats"));
+    let pixel_format = match (bits[0], sample_format[0]) {
+        (8, SAMPLE_FORMAT_UINT) => PixelFormat::Unsigned8,
+        (32, SAMPLE_FORMAT_FLOAT) => PixelFormat::Float32,
+        _ => fail!(invalid("unsupported sample format"))
+    };
+    ensure_image_matches(image_header, width as u32, height as u32, channels, pixel_format.bits())?;
+    ensure!(strip_offsets.len() == strip_byte_counts.len(), invalid("inconsistent strips"));
+
+    let row_length = width * channels * pixel_format.bits() / 8;
+    let size = row_length * height;
+    // Grows with the decoded strips instead of trusting the declared dimensions
+    let mut buf = Vec::with_capacity(size.min(data.len()));
+    for (&offset, &count) in strip_offsets.iter().zip(&strip_byte_counts) {
+        let strip = tiff.bytes(offset as usize, count as usize)?;
+        let expected = row_length * (rows_per_strip as usize).min(height - buf.len() / row_length);
+        match compression {
+            NO_COMPRESSION => buf.extend_from_slice(&strip[..expected.min(strip.len())]),
+            PACK_BITS => unpack_bits(strip, expected, &mut buf)?,
+            _ => fail!(invalid("unsupported compression"))
+        }
+        if buf.len() >= size {
+            break;
+        }
+    }
+    ensure!(buf.len() == size, invalid("truncated pixel data"));
+
+    if pixel_format == PixelFormat::Float32 && tiff.big_endian != cfg!(target_endian = "big") {
+        swap_samples(&mut buf);
+    }
+    match orientation {
+        ORIENTATION_TOP_LEFT => flip_vertically(&mut buf, row_length),
+        ORIENTATION_BOTTOM_LEFT => {},
+        _ => fail!(invalid("unsupported orientation"))
+    }
+    Ok(buf)
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool
+}
+
+impl<'a> Tiff<'a> {
+
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], Error> {
+        match self.data.get(offset..offset.saturating_add(len)) {
+            Some(bytes) => Ok(bytes),
+            None => fail!(invalid("truncated data"))
+        }
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, Error> {
+        let bytes = self.bytes(offset, 2)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, Error> {
+        let bytes = self.bytes(offset, 4)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn values(&self, entry: usize) -> Result<Vec<u32>, Error> {
+        let field_type = self.u16(entry + 2)?;
+        let count = self.u32(entry + 4)? as usize;
+        let size = match field_type {
+            SHORT => 2,
+            LONG => 4,
+            _ => fail!(invalid("unsupported field type"))
+        };
+        let offset = match count * size {
+            0..=4 => entry + 8,
+            len => {
+                let offset = self.u32(entry + 8)? as usize;
+                self.bytes(offset, len)?;
+                offset
+            }
+        };
+        (0..count)
+            .map(|i| match field_type {
+                SHORT => self.u16(offset + 2 * i).map(u32::from),
+                _ => self.u32(offset + 4 * i)
+            })
+            .collect()
+    }
+
+}
+
+fn first(values: Vec<u32>) -> Result<u32, Error> {
+    match values.first() {
+        Some(&value) => Ok(value),
+        None => fail!(invalid("empty field"))
+    }
+}
+
+fn unpack_bits(mut src: &[u8], len: usize, buf: &mut Vec<u8>) -> Result<(), Error> {
+    let target = buf.len() + len;
+    while buf.len() < target && !src.is_empty() {
+        let n = src[0] as i8;
+        src = &src[1..];
+        match n {
+            -128 => {},
+            0.. => {
+                let count = n as usize + 1;
+                ensure!(src.len() >= count, invalid("truncated packbits data"));
+                buf.extend_from_slice(&src[..count]);
+                src = &src[count..];
+            },
+            _ => {
+                ensure!(!src.is_empty(), invalid("truncated packbits data"));
+                let count = 1 - n as isize;
+                buf.resize(buf.len() + count as usize, src[0]);
+                src = &src[1..];
+            }
+        }
+    }
+    buf.truncate(target);
+    Ok(())
+}
+
+fn invalid(reason: &'static str) -> ErrorKind {
+    ErrorKind::InvalidImage { encoding: ImageEncoding::Tiff, reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::test_image_header;
+
+    /// A little endian TIFF with a single strip and `entries` as short fields.
+    fn tiff(entries: &[(u16, u16)], strip: &[u8]) -> Vec<u8> {
+        let count = entries.len() + 2;
+        let strip_offset = 8 + 2 + 12 * count + 4;
+        let mut entries = entries
+            .iter()
+            .map(|&(tag, value)| (tag, SHORT, value as u32))
+            .collect::<Vec<_>>();
+        entries.push((STRIP_OFFSETS, LONG, strip_offset as u32));
+        entries.push((STRIP_BYTE_COUNTS, LONG, strip.len() as u32));
+        entries.sort_by_key(|&(tag, _, _)| tag);
+
+        let mut data = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        data.extend_from_slice(&(count as u16).to_le_bytes());
+        for (tag, field_type, value) in entries {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(strip);
+        data
+    }
+
+    fn gray(width: u16, height: u16, samples: u16, compression: u16, strip: &[u8]) -> Vec<u8> {
+        tiff(&[
+            (IMAGE_WIDTH, width),
+            (IMAGE_LENGTH, height),
+            (BITS_PER_SAMPLE, 8),
+            (COMPRESSION, compression),
+            (SAMPLES_PER_PIXEL, samples)
+        ], strip)
+    }
+
+    fn reason(result: Result<Vec<u8>, Error>) -> &'static str {
+        match result.map_err(Error::into_kind) {
+            Err(ErrorKind::InvalidImage { encoding: ImageEncoding::Tiff, reason }) => reason,
+            other => panic!("expected an invalid image, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn uncompressed() {
+        let data = gray(2, 3, 1, NO_COMPRESSION as u16, &[0, 1, 2, 3, 4, 5]);
+        let image = decode(&data, &test_image_header(2, 3, 1, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [4, 5, 2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn pack_bits() {
+        let data = gray(4, 2, 1, PACK_BITS as u16, &[0xfd, 7, 0x01, 1, 2, 0xff, 9]);
+        let image = decode(&data, &test_image_header(4, 2, 1, PixelFormat::Unsigned8)).unwrap();
+        assert_eq!(image, [1, 2, 9, 9, 7, 7, 7, 7]);
+    }
+
+    #[test]
+    fn zero_samples_per_pixel() {
+        let data = gray(2, 2, 0, NO_COMPRESSION as u16, &[0; 4]);
+        let result = decode(&data, &test_image_header(2, 2, 0, PixelFormat::Unsigned8));
+        assert_eq!(reason(result), "invalid samples per pixel");
+    }
+
+    #[test]
+    fn zero_size() {
+        let data = gray(0, 2, 1, NO_COMPRESSION as u16, &[]);
+        let result = decode(&data, &test_image_header(0, 2, 1, PixelFormat::Unsigned8));
+        assert_eq!(reason(result), "invalid dimensions");
+    }
+
+    #[test]
+    fn truncated() {
+        let data = gray(2, 3, 1, NO_COMPRESSION as u16, &[0, 1, 2, 3, 4, 5]);
+        let header = test_image_header(2, 3, 1, PixelFormat::Unsigned8);
+        for len in 0..data.len() {
+            assert!(decode(&data[..len], &header).is_err(), "{} bytes", len);
+        }
+        let data = gray(4, 2, 1, PACK_BITS as u16, &[0xfd, 7, 0x01, 1]);
+        assert!(decode(&data, &test_image_header(4, 2, 1, PixelFormat::Unsigned8)).is_err());
+    }
+
+}
@@ -1,50 +1,148 @@
-use std::io::Read;
-use bytemuck::{Zeroable, Pod};
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use crate::header::{Field, Layout, Real, RealType};
 
 type UtfResult<T> = std::result::Result<T, std::string::FromUtf8Error>;
-type IoResult<T> = std::io::Result<T>;
+pub type IoResult<T> = std::io::Result<T>;
+
+/// A stream of artery font data that is either read from an [`Read`] or borrowed from a slice.
+pub trait Source<'a> {
+    fn read_bytes(&mut self, len: usize) -> IoResult<Cow<'a, [u8]>>;
+
+    fn read_struct<S: Layout>(&mut self, real: RealType) -> IoResult<S>;
+
+    fn read_struct_array<S: Layout>(&mut self, len: usize, real: RealType) -> IoResult<Cow<'a, [S]>>;
+
+    fn realign(&mut self) -> IoResult<()>;
+
+    /// Discards the next `len` bytes.
+    fn skip(&mut self, len: usize) -> IoResult<()>;
+
+    fn read_string(&mut self, len: usize) -> IoResult<UtfResult<Cow<'a, str>>> {
+        if len > 0 {
+            let buf = self.read_bytes(len + 1)?;
+            self.realign()?;
+            Ok(match buf {
+                Cow::Borrowed(buf) => match std::str::from_utf8(&buf[..len]) {
+                    Ok(str) => Ok(Cow::Borrowed(str)),
+                    Err(_) => String::from_utf8(buf[..len].to_vec()).map(Cow::Owned)
+                },
+                Cow::Owned(mut buf) => {
+                    buf.pop();
+                    String::from_utf8(buf).map(Cow::Owned)
+                }
+            })
+        } else {
+            Ok(Ok(Cow::Borrowed("")))
+        }
+    }
+
+    /// The checksum of all bytes read so far, or `None` if checksum verification is disabled.
+    fn checksum(&self) -> Option<u32>;
+
+    fn bytes_read(&self) -> usize;
+
+    /// The number of bytes left, if known in advance.
+    fn remaining(&self) -> Option<usize>;
+
+    /// Whether [`Source::read_bytes`] and [`Source::read_string`] copy the data into new buffers
+    /// instead of borrowing it.
+    fn allocates(&self) -> bool;
+}
+
+/// The number of bytes [`ReadWrapper::read_struct_array`] reads at once.
+const CHUNK_SIZE: usize = 64 * 1024;
 
 pub struct ReadWrapper<R> {
     inner: R,
     total_length: usize,
-    #[cfg(not(feature = "no-checksum"))]
-    checksum: crate::crc32::Hasher
+    checksum: Option<crate::crc32::Hasher>
 }
 
 impl<R: Read> Read for ReadWrapper<R> {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
         let len = self.inner.read(buf)?;
         self.total_length += len;
-        #[cfg(not(feature = "no-checksum"))]
-        self.checksum.update(&buf[..len]);
+        if let Some(checksum) = &mut self.checksum {
+            checksum.update(&buf[..len]);
+        }
         Ok(len)
     }
 }
 
 impl<R: Read> ReadWrapper<R> {
 
-    pub fn new(inner: R) -> Self {
+    pub fn new(inner: R, verify_checksum: bool) -> Self {
         Self {
             inner,
             total_length: 0,
-            #[cfg(not(feature = "no-checksum"))]
-            checksum: crate::crc32::Hasher::new()
+            checksum: verify_checksum.then(crate::crc32::Hasher::new)
         }
     }
 
-    pub fn read_struct<S: Zeroable + Pod>(&mut self) -> IoResult<S> {
+}
+
+impl<R: Read + Seek> ReadWrapper<R> {
+
+    /// Skips `len` bytes by seeking. The skipped bytes are not hashed, so the checksum can not be verified afterwards.
+    pub fn seek_forward(&mut self, len: usize) -> IoResult<()> {
+        self.inner.seek(SeekFrom::Current(len as i64))?;
+        self.total_length += len;
+        self.checksum = None;
+        Ok(())
+    }
+
+}
+
+impl<'a, R: Read> Source<'a> for ReadWrapper<R> {
+
+    /// Reads incrementally, so that a bogus length fails at the end of the stream instead of allocating it up front.
+    fn read_bytes(&mut self, len: usize) -> IoResult<Cow<'a, [u8]>> {
+        let mut buf = Vec::new();
+        self.by_ref().take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() < len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(Cow::Owned(buf))
+    }
+
+    fn read_struct<S: Layout>(&mut self, real: RealType) -> IoResult<S> {
         let mut result = S::zeroed();
-        self.read_exact(bytemuck::bytes_of_mut(&mut result))?;
+        if is_native(real, SWAP_BYTES) {
+            self.read_exact(bytemuck::bytes_of_mut(&mut result))?;
+        } else {
+            let buf = self.read_bytes(S::file_size(real))?;
+            decode_structs(&buf, real, SWAP_BYTES, std::slice::from_mut(&mut result));
+        }
         Ok(result)
     }
 
-    pub fn read_struct_array<S: Zeroable + Pod + Clone>(&mut self, len: usize) -> IoResult<Vec<S>> {
-        let mut vec = vec![S::zeroed(); len];
-        self.read_exact(bytemuck::cast_slice_mut(&mut vec[..]))?;
-        Ok(vec)
+    /// Decodes chunk by chunk straight into the result, which only grows as data arrives.
+    fn read_struct_array<S: Layout>(&mut self, len: usize, real: RealType) -> IoResult<Cow<'a, [S]>> {
+        let size = S::file_size(real);
+        len.checked_mul(size).ok_or(ErrorKind::UnexpectedEof)?;
+        let chunk_length = (CHUNK_SIZE / size).max(1);
+        let mut vec = Vec::new();
+        let mut buf = Vec::new();
+        while vec.len() < len {
+            let start = vec.len();
+            if start == vec.capacity() {
+                // Doubles the capacity like a regular push would, but never beyond `len`
+                vec.reserve_exact(start.max(chunk_length).min(len - start));
+            }
+            vec.resize(start + chunk_length.min(len - start), S::zeroed());
+            if is_native(real, SWAP_BYTES) {
+                self.read_exact(bytemuck::cast_slice_mut(&mut vec[start..]))?;
+            } else {
+                buf.resize((vec.len() - start) * size, 0);
+                self.read_exact(&mut buf)?;
+                decode_structs(&buf, real, SWAP_BYTES, &mut vec[start..]);
+            }
+        }
+        Ok(Cow::Owned(vec))
     }
 
-    pub fn realign(&mut self) -> IoResult<()> {
+    fn realign(&mut self) -> IoResult<()> {
         let mut dump = [0u8; 4];
         if self.total_length & 0x03 != 0 {
             let len = 0x04 - (self.total_length & 0x03);
@@ -53,25 +151,336 @@ impl<R: Read> ReadWrapper<R> {
         Ok(())
     }
 
-    pub fn read_string(&mut self, len: usize) -> IoResult<UtfResult<String>> {
-        if len > 0 {
-            let mut buf = vec![0u8; len + 1];
-            self.read_exact(buf.as_mut_slice())?;
+    fn skip(&mut self, len: usize) -> IoResult<()> {
+        let skipped = std::io::copy(&mut self.by_ref().take(len as u64), &mut std::io::sink())?;
+        if skipped < len as u64 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn checksum(&self) -> Option<u32> {
+        self.checksum.clone().map(crate::crc32::Hasher::finalize)
+    }
+
+    fn bytes_read(&self) -> usize {
+        self.total_length
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+
+    fn allocates(&self) -> bool {
+        true
+    }
+
+}
+
+pub struct SliceReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    total_length: usize,
+    checksum: Option<crate::crc32::Hasher>,
+    allocates: bool
+}
+
+impl<'a> SliceReader<'a> {
+
+    pub fn new(data: &'a [u8], verify_checksum: bool) -> Self {
+        Self {
+            data,
+            offset: 0,
+            total_length: 0,
+            checksum: verify_checksum.then(crate::crc32::Hasher::new),
+            allocates: false
+        }
+    }
+
+    /// Creates a reader for a part of a file that starts at `offset`. The checksum is not computed.
+    ///
+    /// The caller copies everything that is read out of `data`, so [`Source::allocates`] returns `true`.
+    pub fn with_offset(data: &'a [u8], offset: usize) -> Self {
+        Self {
+            data,
+            offset,
+            total_length: 0,
+            checksum: None,
+            allocates: true
+        }
+    }
+
+    fn take(&mut self, len: usize) -> IoResult<&'a [u8]> {
+        let remaining = &self.data[self.total_length..];
+        if remaining.len() < len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let (taken, _) = remaining.split_at(len);
+        self.total_length += len;
+        if let Some(checksum) = &mut self.checksum {
+            checksum.update(taken);
+        }
+        Ok(taken)
+    }
+
+}
+
+impl<'a> Source<'a> for SliceReader<'a> {
+
+    fn read_bytes(&mut self, len: usize) -> IoResult<Cow<'a, [u8]>> {
+        self.take(len).map(Cow::Borrowed)
+    }
+
+    fn read_struct<S: Layout>(&mut self, real: RealType) -> IoResult<S> {
+        let mut result = S::zeroed();
+        decode_structs(self.take(S::file_size(real))?, real, SWAP_BYTES, std::slice::from_mut(&mut result));
+        Ok(result)
+    }
+
+    fn read_struct_array<S: Layout>(&mut self, len: usize, real: RealType) -> IoResult<Cow<'a, [S]>> {
+        let size = len.checked_mul(S::file_size(real)).ok_or(ErrorKind::UnexpectedEof)?;
+        let bytes = self.take(size)?;
+        if is_native(real, SWAP_BYTES) {
+            if let Ok(slice) = bytemuck::try_cast_slice(bytes) {
+                return Ok(Cow::Borrowed(slice));
+            }
+        }
+        let mut vec = vec![S::zeroed(); len];
+        decode_structs(bytes, real, SWAP_BYTES, &mut vec);
+        Ok(Cow::Owned(vec))
+    }
+
+    fn realign(&mut self) -> IoResult<()> {
+        if self.bytes_read() & 0x03 != 0 {
+            let len = 0x04 - (self.bytes_read() & 0x03);
+            self.take(len)?;
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, len: usize) -> IoResult<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn checksum(&self) -> Option<u32> {
+        self.checksum.clone().map(crate::crc32::Hasher::finalize)
+    }
+
+    fn bytes_read(&self) -> usize {
+        self.offset + self.total_length
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.data.len() - self.total_length)
+    }
+
+    fn allocates(&self) -> bool {
+        self.allocates
+    }
+
+}
+
+/// Whether the fields of the little endian file format have to be byte swapped on this host.
+pub const SWAP_BYTES: bool = cfg!(target_endian = "big");
+
+/// Whether structs stored with the floating point type `real` can be copied into memory as they are.
+pub fn is_native(real: RealType, swap: bool) -> bool {
+    !swap && real == RealType::native()
+}
+
+/// Decodes `structs.len()` structs from `bytes`, converting reals stored as `real` into [`Real`]
+/// and swapping the byte order of every field if `swap` is set.
+#[allow(clippy::unnecessary_cast)]
+pub fn decode_structs<S: Layout>(bytes: &[u8], real: RealType, swap: bool, structs: &mut [S]) {
+    if is_native(real, swap) {
+        bytemuck::cast_slice_mut(structs).copy_from_slice(bytes);
+        return;
+    }
+    let mut src = bytes;
+    for item in structs.iter_mut() {
+        let mut dst = bytemuck::bytes_of_mut(item);
+        for &(field, count) in S::FIELDS {
+            for _ in 0..count {
+                let (value, rest) = src.split_at(field.size(real));
+                src = rest;
+                let (target, rest) = std::mem::take(&mut dst).split_at_mut(field.size(RealType::native()));
+                dst = rest;
+                match field {
+                    Field::U8 => target.copy_from_slice(value),
+                    Field::U32 => {
+                        let value = u32::from_ne_bytes(value.try_into().unwrap());
+                        target.copy_from_slice(&swap_if(value, swap, u32::swap_bytes).to_ne_bytes());
+                    },
+                    Field::Real => {
+                        let value = match real {
+                            RealType::F32 => f32::from_bits(swap_if(u32::from_ne_bytes(value.try_into().unwrap()), swap, u32::swap_bytes)) as Real,
+                            RealType::F64 => f64::from_bits(swap_if(u64::from_ne_bytes(value.try_into().unwrap()), swap, u64::swap_bytes)) as Real
+                        };
+                        target.copy_from_slice(&value.to_ne_bytes());
+                    }
+                }
+            }
+        }
+        debug_assert!(dst.is_empty());
+    }
+}
+
+/// Encodes `structs` into the file format, swapping the byte order of every field if `swap` is set.
+pub fn encode_structs<S: Layout>(structs: &[S], swap: bool) -> Cow<'_, [u8]> {
+    if !swap {
+        return Cow::Borrowed(bytemuck::cast_slice(structs));
+    }
+    let mut bytes = bytemuck::cast_slice::<S, u8>(structs).to_vec();
+    for mut item in bytes.chunks_exact_mut(std::mem::size_of::<S>()) {
+        for &(field, count) in S::FIELDS {
+            for _ in 0..count {
+                let (value, rest) = std::mem::take(&mut item).split_at_mut(field.size(RealType::native()));
+                item = rest;
+                if field != Field::U8 {
+                    value.reverse();
+                }
+            }
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+fn swap_if<T>(value: T, swap: bool, f: fn(T) -> T) -> T {
+    match swap {
+        true => f(value),
+        false => value
+    }
+}
+
+pub struct WriteWrapper<W> {
+    inner: W,
+    total_length: usize,
+    checksum: crate::crc32::Hasher
+}
+
+impl<W: Write> Write for WriteWrapper<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let len = self.inner.write(buf)?;
+        self.total_length += len;
+        self.checksum.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> WriteWrapper<W> {
+
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            total_length: 0,
+            checksum: crate::crc32::Hasher::new()
+        }
+    }
+
+    pub fn write_struct<S: Layout>(&mut self, value: &S) -> IoResult<()> {
+        self.write_struct_array(std::slice::from_ref(value))
+    }
+
+    pub fn write_struct_array<S: Layout>(&mut self, values: &[S]) -> IoResult<()> {
+        self.write_all(&encode_structs(values, SWAP_BYTES))
+    }
+
+    pub fn realign(&mut self) -> IoResult<()> {
+        if self.total_length & 0x03 != 0 {
+            let len = 0x04 - (self.total_length & 0x03);
+            self.write_all(&[0u8; 4][..len])?;
+        }
+        Ok(())
+    }
+
+    pub fn write_string(&mut self, string: &str) -> IoResult<()> {
+        if !string.is_empty() {
+            self.write_all(string.as_bytes())?;
+            self.write_all(&[0u8])?;
             self.realign()?;
-            buf.pop();
-            Ok(String::from_utf8(buf))
-        } else {
-            Ok(Ok(String::new()))
         }
+        Ok(())
     }
 
-    #[cfg(not(feature = "no-checksum"))]
     pub fn checksum(&self) -> u32 {
         self.checksum.clone().finalize()
     }
 
-    pub fn bytes_read(&self) -> usize {
+    pub fn bytes_written(&self) -> usize {
         self.total_length
     }
 
 }
+
+pub fn aligned_length(len: usize) -> usize {
+    (len + 0x03) & !0x03
+}
+
+/// The number of bytes a string of `len` bytes occupies in the file, including the terminator and padding.
+pub fn string_length(len: usize) -> usize {
+    match len {
+        0 => 0,
+        len => aligned_length(len + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::ArteryFontHeader;
+    use crate::structs::Glyph;
+    use bytemuck::Zeroable;
+
+    const TAG: &[u8; 16] = b"ARTERY/FONT\0\0\0\0\0";
+    const REALS: [f32; 10] = [0.5, -1.25, 2.0, 3.75, -0.125, 0.0, 1.0, 16.5, -64.0, 0.625];
+
+    /// A byte buffer in the opposite byte order of the host.
+    fn swapped_u32(values: impl IntoIterator<Item = u32>) -> Vec<u8> {
+        values.into_iter().flat_map(|value| value.swap_bytes().to_ne_bytes()).collect()
+    }
+
+    fn swapped_reals(real: RealType) -> Vec<u8> {
+        match real {
+            RealType::F32 => REALS.iter().flat_map(|value| value.to_bits().swap_bytes().to_ne_bytes()).collect(),
+            RealType::F64 => REALS.iter().flat_map(|&value| (value as f64).to_bits().swap_bytes().to_ne_bytes()).collect()
+        }
+    }
+
+    #[test]
+    fn decode_swapped_header() {
+        let words = (0..24).map(|i| 0x0102_0300 + i);
+        let mut bytes = TAG.to_vec();
+        bytes.extend(swapped_u32(words.clone()));
+        let mut header = ArteryFontHeader::zeroed();
+        decode_structs(&bytes, RealType::native(), true, std::slice::from_mut(&mut header));
+        assert_eq!(&header.tag, TAG);
+        assert_eq!(header.magic_no, 0x0102_0300);
+        assert_eq!(header.real_type, 0x0102_0303);
+        assert_eq!(bytemuck::cast_slice::<u8, u32>(&bytemuck::bytes_of(&header)[16..]), words.collect::<Vec<_>>());
+        assert_eq!(encode_structs(std::slice::from_ref(&header), true), bytes);
+    }
+
+    #[test]
+    fn decode_swapped_glyphs() {
+        for real in [RealType::F32, RealType::F64] {
+            let mut bytes = swapped_u32(['A' as u32, 3]);
+            bytes.extend(swapped_reals(real));
+            bytes.extend(bytes.clone());
+            let mut glyphs = [Glyph::zeroed(); 2];
+            decode_structs(&bytes, real, true, &mut glyphs);
+            for glyph in glyphs {
+                assert_eq!((glyph.codepoint, glyph.image), ('A' as u32, 3), "{:?}", real);
+                assert_eq!((glyph.plane_bounds.left, glyph.plane_bounds.bottom), (0.5, -1.25), "{:?}", real);
+                assert_eq!(glyph.image_bounds.top, 16.5, "{:?}", real);
+                assert_eq!((glyph.advance.horizontal, glyph.advance.vertical), (-64.0, 0.625), "{:?}", real);
+            }
+            if real == RealType::native() {
+                assert_eq!(encode_structs(&glyphs, true), bytes);
+            }
+        }
+    }
+}
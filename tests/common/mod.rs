@@ -0,0 +1,147 @@
+#![allow(dead_code)]
+
+use artery_font::*;
+
+#[cfg(not(feature = "double"))]
+pub type Real = f32;
+
+#[cfg(feature = "double")]
+pub type Real = f64;
+
+pub fn rect(left: Real, bottom: Real, right: Real, top: Real) -> Rect {
+    Rect {
+        left,
+        bottom,
+        right,
+        top
+    }
+}
+
+/// A glyph whose atlas region is the unit square.
+pub fn glyph(character: char, advance: Real, plane_bounds: Rect) -> Glyph {
+    Glyph {
+        codepoint: character as u32,
+        image: 0,
+        plane_bounds,
+        image_bounds: rect(0.0, 0.0, 1.0, 1.0),
+        advance: Advance {
+            horizontal: advance,
+            vertical: 0.0
+        }
+    }
+}
+
+/// A glyph without a visible shape, like a space.
+pub fn blank(character: char, advance: Real) -> Glyph {
+    Glyph {
+        image_bounds: rect(0.0, 0.0, 0.0, 0.0),
+        ..glyph(character, advance, rect(0.0, 0.0, 0.0, 0.0))
+    }
+}
+
+pub fn kern_pair(first: char, second: char, horizontal: Real) -> KernPair {
+    KernPair {
+        codepoint1: first as u32,
+        codepoint2: second as u32,
+        advance: Advance {
+            horizontal,
+            vertical: 0.0
+        }
+    }
+}
+
+/// A unicode variant with an em size of one, an ascender of `0.75`, a descender of `-0.25` and a line height of `1.25`.
+pub fn variant(glyphs: Vec<Glyph>, kern_pairs: Vec<KernPair>) -> FontVariant {
+    FontVariant {
+        flags: 0,
+        weight: 400,
+        codepoint_type: CodepointType::Unicode,
+        image_type: ImageType::Msdf,
+        fallback_variant: 0,
+        fallback_glyph: 0,
+        metrics: FontMetric {
+            font_size: 32.0,
+            distance_range: 4.0,
+            em_size: 1.0,
+            ascender: 0.75,
+            descender: -0.25,
+            line_height: 1.25,
+            underline_y: -0.125,
+            underline_thickness: 0.0625
+        },
+        name: String::from("Regular"),
+        metadata: String::new(),
+        glyphs,
+        kern_pairs
+    }
+}
+
+/// A layouter for `variant` with a single atlas image.
+pub fn layouter(variant: &FontVariant) -> TextLayouter {
+    TextLayouter::new(variant, &[(1, 1)])
+}
+
+/// The union of the plane bounds of all quads of `layout`.
+pub fn quad_bounds(layout: &TextLayout) -> Option<Rect> {
+    layout.quads().map(|quad| quad.plane).reduce(|a, b| Rect {
+        left: a.left.min(b.left),
+        bottom: a.bottom.min(b.bottom),
+        right: a.right.max(b.right),
+        top: a.top.max(b.top)
+    })
+}
+
+pub const TEST_FONT: &[u8] = include_bytes!("../../example/data/test.arfont");
+
+pub fn write(font: &ArteryFont) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    font.write(&mut bytes).unwrap();
+    bytes
+}
+
+/// Options that leave the images encoded, so fonts with PNG atlases can be read without the `png` feature.
+pub fn lazy() -> ReadOptions {
+    ReadOptions {
+        decode_images: false,
+        ..Default::default()
+    }
+}
+
+/// The test font with json metadata, a second variant, a raw image and two appendices whose lengths are not aligned.
+pub fn sample_font() -> Vec<u8> {
+    let mut font = ArteryFont::read_lazy(TEST_FONT).unwrap();
+    font.metadata_format = MetadataFormat::Json(String::from("{\"name\":\"test\"}"));
+    let mut bold = font.variants[0].clone();
+    bold.name = String::from("Bold");
+    bold.weight = 700;
+    bold.glyphs.truncate(10);
+    font.variants.push(bold);
+    font.images.push(Image {
+        flags: 0,
+        width: 3,
+        height: 1,
+        channels: 1,
+        pixel_format: PixelFormat::Unsigned8,
+        image_type: ImageType::SrgbImage,
+        child_images: 0,
+        texture_flags: 0,
+        metadata: String::from("raw"),
+        data: vec![1, 2, 3],
+        orientation: ImageOrientation::BottomUp,
+        encoded: None
+    });
+    font.appendices.push(Appendix {
+        metadata: String::from("first"),
+        data: vec![1, 2, 3, 4, 5]
+    });
+    font.appendices.push(Appendix {
+        metadata: String::new(),
+        data: vec![6]
+    });
+    write(&font)
+}
+
+/// The left, bottom, right and top edge of `rect`, for comparisons.
+pub fn edges(rect: Rect) -> [Real; 4] {
+    [rect.left, rect.bottom, rect.right, rect.top]
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+/// Reads `bytes` with both the streaming reader and the borrowed parser, which have to fail the same way.
+fn read_err(bytes: &[u8], options: ReadOptions) -> Error {
+    let options = ReadOptions {
+        decode_images: false,
+        ..options
+    };
+    let err = ArteryFont::read_with(bytes, options).unwrap_err();
+    let parse_err = ArteryFontRef::parse_with(bytes, options).unwrap_err();
+    assert_eq!(format!("{:?}", err.kind()), format!("{:?}", parse_err.kind()));
+    assert_eq!(err.location(), parse_err.location());
+    err
+}
+
+fn section(err: &Error) -> Option<Section> {
+    err.location().map(|location| location.section)
+}
+
+fn patched(offset: usize, value: u32) -> Vec<u8> {
+    let mut bytes = TEST_FONT.to_vec();
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn bad_header_magic() {
+    let mut bytes = TEST_FONT.to_vec();
+    bytes[0] ^= 0xff;
+    let err = read_err(&bytes, ReadOptions::default());
+    assert!(matches!(err.kind(), ErrorKind::BadMagic), "{}", err);
+    assert_eq!(err.location(), Some(Location { section: Section::Header, offset: 112 }));
+}
+
+#[test]
+fn unsupported_real_type() {
+    // real_type
+    let err = read_err(&patched(28, 0x99), ReadOptions::default());
+    assert!(matches!(err.kind(), ErrorKind::UnsupportedRealType(0x99)), "{}", err);
+    assert_eq!(section(&err), Some(Section::Header));
+}
+
+#[test]
+fn bad_footer_magic() {
+    let err = read_err(&patched(TEST_FONT.len() - 28, 0), ReadOptions::default());
+    assert!(matches!(err.kind(), ErrorKind::BadMagic), "{}", err);
+    assert_eq!(err.location(), Some(Location { section: Section::Footer, offset: TEST_FONT.len() - 4 }));
+}
+
+#[test]
+fn checksum_mismatch() {
+    let bytes = patched(TEST_FONT.len() - 4, 0x1234_5678);
+    let options = ReadOptions {
+        verify_checksum: true,
+        ..Default::default()
+    };
+    let err = read_err(&bytes, options);
+    assert!(matches!(err.kind(), &ErrorKind::ChecksumMismatch { expected: 0x1234_5678, .. }), "{}", err);
+    assert_eq!(err.location(), Some(Location { section: Section::Footer, offset: TEST_FONT.len() }));
+
+    let options = ReadOptions {
+        verify_checksum: false,
+        ..lazy()
+    };
+    assert!(ArteryFont::read_with(&bytes[..], options).is_ok());
+    assert!(ArteryFontRef::parse_with(&bytes, options).is_ok());
+}
+
+#[test]
+fn wrong_total_length() {
+    let options = ReadOptions {
+        verify_checksum: false,
+        ..Default::default()
+    };
+    // total_length
+    let err = read_err(&patched(TEST_FONT.len() - 8, 12), options);
+    assert!(matches!(err.kind(), &ErrorKind::LengthMismatch { expected: 12, actual } if actual == TEST_FONT.len()), "{}", err);
+    assert_eq!(section(&err), Some(Section::Footer));
+}
+
+#[test]
+fn truncated() {
+    let options = lazy();
+    // Every cut through the headers and a sample of cuts through the payloads
+    let lengths = (0..TEST_FONT.len()).filter(|&len| len < 512 || len % 61 == 0 || len + 64 > TEST_FONT.len());
+    for len in lengths {
+        let bytes = &TEST_FONT[..len];
+        let err = ArteryFont::read_with(bytes, options).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof), "{} bytes: {}", len, err);
+        assert!(err.location().unwrap().offset <= len, "{} bytes: {}", len, err);
+
+        let err = ArteryFontRef::parse_with(bytes, options).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Io(_) | ErrorKind::LengthMismatch { .. }), "{} bytes: {}", len, err);
+        assert!(err.location().is_some(), "{} bytes: {}", len, err);
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+/// Three unicode variants: `a` falls back to `b`, which falls back to `c`, which falls back to `a` again.
+fn font() -> ArteryFont {
+    let mut a = variant(vec![glyph('a', 1.0, rect(0.0, 0.0, 1.0, 1.0)), glyph('?', 1.0, rect(0.0, 0.0, 1.0, 1.0))], Vec::new());
+    let mut b = variant(vec![glyph('b', 1.0, rect(0.0, 0.0, 1.0, 1.0))], Vec::new());
+    let mut c = variant(vec![glyph('c', 1.0, rect(0.0, 0.0, 1.0, 1.0)), glyph('a', 2.0, rect(0.0, 0.0, 1.0, 1.0))], Vec::new());
+    a.fallback_variant = 1;
+    a.fallback_glyph = '?' as u32;
+    b.fallback_variant = 2;
+    c.fallback_variant = 0;
+    ArteryFont {
+        metadata_format: MetadataFormat::None,
+        variants: vec![a, b, c],
+        images: Vec::new(),
+        appendices: Vec::new()
+    }
+}
+
+fn summary(resolved: Result<ResolvedGlyph<'_>, LookupError>) -> Result<(usize, u32, bool), LookupError> {
+    resolved.map(|resolved| (resolved.variant_index, resolved.glyph.codepoint, resolved.substituted))
+}
+
+#[test]
+fn resolver_matches_resolve_glyph() {
+    let font = font();
+    let resolver = font.glyph_resolver();
+    for variant in 0..4 {
+        for codepoint in [Codepoint::Unicode('a'), 'b'.into(), 'c'.into(), '?'.into(), 'x'.into(), Codepoint::Index(0)] {
+            assert_eq!(summary(resolver.resolve(variant, codepoint)), summary(font.resolve_glyph(variant, codepoint)), "{} in {}", codepoint, variant);
+        }
+    }
+    assert_eq!(summary(resolver.resolve(1, 'a')), Ok((2, 'a' as u32, false)));
+}
+
+#[test]
+fn fallback_variants_are_followed() {
+    let font = font();
+    assert_eq!(summary(font.resolve_glyph(0, 'a')), Ok((0, 'a' as u32, false)));
+    assert_eq!(summary(font.resolve_glyph(0, 'b')), Ok((1, 'b' as u32, false)));
+    assert_eq!(summary(font.resolve_glyph(0, 'c')), Ok((2, 'c' as u32, false)));
+    // The chain of `b` wraps around to `a`, but `c` has its own `a`
+    assert_eq!(summary(font.resolve_glyph(1, 'a')), Ok((2, 'a' as u32, false)));
+    assert_eq!(summary(font.resolve_glyph(2, 'b')), Ok((1, 'b' as u32, false)));
+    assert_eq!(font.resolve_glyph(1, 'a').unwrap().glyph.advance.horizontal, 2.0);
+}
+
+#[test]
+fn cycles_end_with_missing_glyphs() {
+    let font = font();
+    // Only `a` has a fallback glyph
+    assert_eq!(summary(font.resolve_glyph(0, 'x')), Ok((0, '?' as u32, true)));
+    assert_eq!(summary(font.resolve_glyph(1, 'x')), Err(LookupError::Missing('x'.into())));
+    assert_eq!(summary(font.resolve_glyph(2, 'x')), Err(LookupError::Missing('x'.into())));
+    assert_eq!(summary(font.resolve_glyph(3, 'a')), Err(LookupError::UnknownVariant(3)));
+}
+
+#[test]
+fn chains_end_at_unknown_and_own_variants() {
+    let mut font = font();
+    font.variants[1].fallback_variant = 7;
+    font.variants[2].fallback_variant = 2;
+    // `a` no longer reaches `c` and substitutes its fallback glyph
+    assert_eq!(summary(font.resolve_glyph(0, 'c')), Ok((0, '?' as u32, true)));
+    assert_eq!(summary(font.resolve_glyph(1, 'a')), Err(LookupError::Missing('a'.into())));
+    assert_eq!(summary(font.resolve_glyph(2, 'b')), Err(LookupError::Missing('b'.into())));
+    assert_eq!(summary(font.resolve_glyph(2, 'a')), Ok((2, 'a' as u32, false)));
+}
+
+#[test]
+fn fallback_glyphs_follow_the_chain() {
+    let mut font = font();
+    // `b` falls back to `?`, which only `a` contains
+    font.variants[1].fallback_glyph = '?' as u32;
+    assert_eq!(summary(font.resolve_glyph(1, 'x')), Ok((0, '?' as u32, true)));
+    // A codepoint that is found somewhere in the chain wins over the fallback glyph
+    assert_eq!(summary(font.resolve_glyph(1, 'c')), Ok((2, 'c' as u32, false)));
+}
+
+#[test]
+fn codepoint_type_mismatches_are_reported() {
+    let mut font = font();
+    font.variants[0].codepoint_type = CodepointType::Indexed;
+    font.variants[0].fallback_glyph = 0;
+    let mismatch = LookupError::CodepointTypeMismatch {
+        expected: CodepointType::Indexed,
+        actual: CodepointType::Unicode
+    };
+    assert_eq!(summary(font.resolve_glyph(0, 'x')), Err(mismatch));
+    // The unicode fallback variants can still provide the glyph
+    assert_eq!(summary(font.resolve_glyph(0, 'b')), Ok((1, 'b' as u32, false)));
+}
+
+#[test]
+fn borrowed_fonts_resolve_like_owned_fonts() {
+    let bytes = write(&font());
+    let font = ArteryFontRef::parse(&bytes).unwrap();
+    let owned = font.clone().into_owned();
+    let resolver = font.glyph_resolver();
+    for variant in 0..4 {
+        for codepoint in ['a', 'b', 'c', '?', 'x'] {
+            let expected = summary(owned.resolve_glyph(variant, codepoint));
+            let resolved = font.resolve_glyph(variant, codepoint).map(|resolved| (resolved.variant_index, resolved.glyph.codepoint, resolved.substituted));
+            assert_eq!(resolved, expected, "{} in {}", codepoint, variant);
+            let resolved = resolver.resolve(variant, codepoint).map(|resolved| (resolved.variant_index, resolved.glyph.codepoint, resolved.substituted));
+            assert_eq!(resolved, expected, "{} in {}", codepoint, variant);
+        }
+    }
+}
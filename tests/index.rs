@@ -0,0 +1,96 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+/// A deterministic sequence of pseudo random numbers below `bound`.
+fn numbers(seed: u64, bound: u32) -> impl Iterator<Item = u32> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) % bound as u64) as u32
+    })
+}
+
+fn character(value: u32) -> char {
+    char::from_u32('A' as u32 + value).unwrap()
+}
+
+/// The codepoint and advance of a lookup result, which identify the glyph in these tests.
+fn found(result: Result<&Glyph, LookupError>) -> Result<(u32, Real), LookupError> {
+    result.map(|glyph| (glyph.codepoint, glyph.advance.horizontal))
+}
+
+#[test]
+fn glyph_index_matches_linear_scan() {
+    // Duplicate codepoints get different advances, so the first glyph has to win in both lookups
+    let glyphs = numbers(5, 40)
+        .take(100)
+        .enumerate()
+        .map(|(i, value)| glyph(character(value), i as Real, rect(0.0, 0.0, 1.0, 1.0)))
+        .collect();
+    let variant = variant(glyphs, Vec::new());
+    let index = variant.glyph_index();
+    assert!(index.glyphs().windows(2).all(|pair| pair[0].codepoint < pair[1].codepoint));
+    for value in 0..50 {
+        let character = character(value);
+        assert_eq!(found(index.glyph(character)), found(variant.find_glyph(character)), "{:?}", character);
+        assert_eq!(index.contains(character), variant.find_glyph(character).is_ok());
+    }
+    assert_eq!(index.glyph(Codepoint::Index(0)).unwrap_err(), variant.find_glyph(Codepoint::Index(0)).unwrap_err());
+}
+
+#[test]
+fn glyph_index_respects_codepoint_type() {
+    let mut variant = variant(vec![glyph('A', 1.0, rect(0.0, 0.0, 1.0, 1.0))], Vec::new());
+    variant.codepoint_type = CodepointType::Indexed;
+    let mismatch = LookupError::CodepointTypeMismatch {
+        expected: CodepointType::Indexed,
+        actual: CodepointType::Unicode
+    };
+    assert_eq!(variant.glyph_index().glyph('A').unwrap_err(), mismatch);
+    assert_eq!(variant.find_glyph('A').unwrap_err(), mismatch);
+    assert!(variant.glyph_index().contains(Codepoint::Index('A' as u32)));
+
+    variant.codepoint_type = CodepointType::Unspecified;
+    for codepoint in [Codepoint::Unicode('A'), Codepoint::Index('A' as u32), Codepoint::Icon('A' as u32)] {
+        assert_eq!(found(variant.glyph_index().glyph(codepoint)), Ok(('A' as u32, 1.0)));
+        assert_eq!(found(variant.find_glyph(codepoint)), Ok(('A' as u32, 1.0)));
+    }
+}
+
+#[test]
+fn glyph_index_finds_every_glyph_of_the_test_font() {
+    let font = ArteryFontRef::parse_with(TEST_FONT, lazy()).unwrap();
+    let variant = &font.variants[0];
+    let index = variant.glyph_index();
+    for glyph in variant.glyphs.iter() {
+        let codepoint = Codepoint::new(variant.codepoint_type, glyph.codepoint).unwrap();
+        assert_eq!(found(index.glyph(codepoint)), found(variant.find_glyph(codepoint)));
+        assert_eq!(index.glyph(codepoint).unwrap().codepoint, glyph.codepoint);
+    }
+}
+
+#[test]
+fn kerning_index_matches_linear_scan() {
+    let mut values = numbers(17, 24);
+    let kern_pairs: Vec<KernPair> = (0..300)
+        .map(|i| kern_pair(character(values.next().unwrap()), character(values.next().unwrap()), i as Real))
+        .collect();
+    let index = KerningIndex::new(CodepointType::Unicode, &kern_pairs);
+    let mut distinct = 0;
+    for first in 0..26 {
+        for second in 0..26 {
+            let (first, second) = (character(first), character(second));
+            let expected = kern_pairs
+                .iter()
+                .find(|pair| pair.codepoint1 == first as u32 && pair.codepoint2 == second as u32)
+                .map(|pair| pair.advance);
+            assert_eq!(index.get(first, second), expected, "{:?} {:?}", first, second);
+            assert_eq!(index.kerning(first, second), expected.unwrap_or_default());
+            distinct += expected.is_some() as usize;
+        }
+    }
+    assert_eq!(index.len(), distinct);
+    assert_eq!(index.get(Codepoint::Index('A' as u32), 'A'), None);
+}
@@ -0,0 +1,476 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+const ALEF: char = '\u{05D0}';
+const BET: char = '\u{05D1}';
+
+/// Latin and hebrew letters, digits and punctuation with advances that are exact in binary, and `?` as fallback glyph.
+fn letters() -> FontVariant {
+    let mut variant = variant(vec![
+        blank(' ', 0.25),
+        glyph('a', 0.5, rect(0.0, 0.0, 0.5, 0.5)),
+        glyph('b', 0.5, rect(0.0, 0.0, 0.5, 0.75)),
+        glyph('w', 2.0, rect(0.0, 0.0, 2.0, 0.5)),
+        glyph('-', 0.25, rect(0.0, 0.25, 0.25, 0.375)),
+        glyph('?', 0.5, rect(0.0, 0.0, 0.5, 0.75)),
+        glyph('1', 0.5, rect(0.0, 0.0, 0.5, 0.75)),
+        glyph('2', 0.5, rect(0.0, 0.0, 0.5, 0.75)),
+        glyph(ALEF, 0.5, rect(0.0, 0.0, 0.5, 0.5)),
+        glyph(BET, 0.5, rect(0.0, 0.0, 0.5, 0.5))
+    ], Vec::new());
+    variant.fallback_glyph = '?' as u32;
+    variant
+}
+
+/// The characters and horizontal positions of the glyphs of a layout in visual order.
+fn positions(layout: &TextLayout) -> Vec<(char, Real)> {
+    layout.glyphs.iter().map(|glyph| (glyph.character, glyph.x)).collect()
+}
+
+/// The byte ranges of the lines of a layout.
+fn line_texts(layout: &TextLayout) -> Vec<std::ops::Range<usize>> {
+    layout.lines.iter().map(|line| line.text.clone()).collect()
+}
+
+#[test]
+fn glyphs_are_placed_on_the_baseline() {
+    let layout = layouter(&letters()).layout("ab", &LayoutOptions::default());
+    assert_eq!(positions(&layout), [('a', 0.0), ('b', 0.5)]);
+    assert!(layout.glyphs.iter().all(|glyph| glyph.y == -0.75 && glyph.line == 0 && glyph.advance == 0.5));
+    assert_eq!(layout.glyphs[1].index, 1);
+    assert_eq!(edges(layout.glyphs[1].quad.unwrap().plane), [0.5, -0.75, 1.0, 0.0]);
+    assert_eq!((layout.width, layout.height), (1.0, 1.0));
+
+    let layout = layouter(&letters()).layout("ab", &LayoutOptions {
+        size: 32.0,
+        ..Default::default()
+    });
+    assert_eq!(positions(&layout), [('a', 0.0), ('b', 16.0)]);
+    assert_eq!(edges(layout.glyphs[1].quad.unwrap().plane), [16.0, -24.0, 32.0, 0.0]);
+    assert_eq!((layout.width, layout.height), (32.0, 32.0));
+}
+
+#[test]
+fn uvs_match_the_atlas_orientation() {
+    let mut variant = letters();
+    variant.glyphs[1].image_bounds = rect(0.0, 1.0, 1.0, 3.0);
+    variant.glyphs[2].image = 1;
+    let layouter = TextLayouter::new(&variant, &[(2, 4)]);
+    let layout = layouter.layout("ab", &LayoutOptions::default());
+    assert_eq!(edges(layout.glyphs[0].quad.unwrap().uv), [0.0, 0.25, 0.5, 0.75]);
+    // `b` refers to an image that is not in the atlas
+    assert!(layout.glyphs[1].quad.is_none());
+
+    let layout = layouter.layout("a", &LayoutOptions {
+        orientation: ImageOrientation::TopDown,
+        ..Default::default()
+    });
+    assert_eq!(edges(layout.glyphs[0].quad.unwrap().uv), [0.0, 0.75, 0.5, 0.25]);
+}
+
+#[test]
+fn kerning_fallback_and_blank_glyphs() {
+    let mut variant = letters();
+    variant.kern_pairs.push(kern_pair('a', 'b', -0.125));
+    let layout = layouter(&variant).layout("ab x", &LayoutOptions::default());
+    // `x` is replaced by the fallback glyph
+    assert_eq!(positions(&layout), [('a', 0.0), ('b', 0.375), (' ', 0.875), ('x', 1.125)]);
+    assert!(layout.glyphs[2].quad.is_none());
+    assert_eq!(edges(layout.glyphs[3].quad.unwrap().plane), [1.125, -0.75, 1.625, 0.0]);
+
+    variant.fallback_glyph = 0;
+    // Without a fallback glyph `x` is skipped and its neighbours are kerned
+    let layout = layouter(&variant).layout("axb", &LayoutOptions::default());
+    assert_eq!(positions(&layout), [('a', 0.0), ('b', 0.375)]);
+    assert_eq!(layout.glyphs[1].index, 2);
+}
+
+#[test]
+fn line_breaks_start_new_lines() {
+    let layout = layouter(&letters()).layout("ab\r\na\n", &LayoutOptions::default());
+    assert_eq!(line_texts(&layout), [0..2, 4..5, 6..6]);
+    assert_eq!(layout.lines.iter().map(|line| line.baseline).collect::<Vec<_>>(), [-0.75, -2.0, -3.25]);
+    assert!(layout.lines.iter().all(|line| !line.wrapped && line.ascender == 0.75 && line.descender == -0.25));
+    assert_eq!(positions(&layout), [('a', 0.0), ('b', 0.5), ('a', 0.0)]);
+    assert_eq!((layout.glyphs[2].y, layout.glyphs[2].line), (-2.0, 1));
+    assert_eq!((layout.width, layout.height), (1.0, 3.5));
+}
+
+/// Hebrew letters and brackets whose ink is on different sides, so mirroring them changes the bounds.
+fn brackets() -> FontVariant {
+    variant(vec![
+        blank(' ', 0.25),
+        glyph('(', 1.0, rect(0.0, 0.0, 0.25, 0.75)),
+        glyph(')', 1.0, rect(0.75, 0.0, 1.0, 0.75)),
+        glyph(ALEF, 1.0, rect(0.0, 0.0, 1.0, 0.5))
+    ], vec![
+        kern_pair(')', ALEF, -0.5)
+    ])
+}
+
+#[test]
+fn mirrored_brackets_are_measured_as_drawn() {
+    let layouter = layouter(&brackets());
+    let text = format!("{}(", ALEF);
+    let layout = layouter.layout(&text, &LayoutOptions::default());
+    let bracket = layout.glyphs.iter().find(|glyph| glyph.character == '(').unwrap();
+    assert!(bracket.right_to_left);
+    assert_eq!(bracket.shaped, ')');
+
+    let metrics = layouter.measure(&text, &LayoutOptions::default());
+    let ink = metrics.ink_bounds.unwrap();
+    let drawn = quad_bounds(&layout).unwrap();
+    assert_eq!((ink.left, ink.right), (drawn.left, drawn.right));
+    // The mirrored bracket is drawn left of the letter, which is kerned by half an em and overlaps its ink
+    assert_eq!(metrics.width, 1.5);
+    assert_eq!((ink.left, ink.right), (0.5, 1.5));
+}
+
+#[test]
+fn mirrored_brackets_are_wrapped_as_drawn() {
+    let layouter = layouter(&brackets());
+    let text = format!("{}( {}(", ALEF, ALEF);
+    let options = LayoutOptions {
+        max_width: Some(1.75),
+        ..Default::default()
+    };
+    let layout = layouter.layout(&text, &options);
+    assert_eq!(layout.lines.len(), 2);
+    for line in &layout.lines {
+        assert_eq!(line.width, 1.5);
+    }
+}
+
+fn wrapped(text: &str, max_width: Real) -> TextLayout {
+    layouter(&letters()).layout(text, &LayoutOptions {
+        max_width: Some(max_width),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn lines_wrap_after_whitespace() {
+    let layout = wrapped("aa aa", 1.0);
+    assert_eq!(line_texts(&layout), [0..3, 3..5]);
+    assert!(layout.lines[0].wrapped && !layout.lines[1].wrapped);
+    // The hanging space does not count towards the width
+    assert_eq!((layout.lines[0].width, layout.lines[1].width), (1.0, 1.0));
+    assert_eq!(positions(&layout), [('a', 0.0), ('a', 0.5), (' ', 1.0), ('a', 0.0), ('a', 0.5)]);
+    assert_eq!((layout.glyphs[3].line, layout.glyphs[3].y), (1, -2.0));
+
+    let layout = wrapped("aa   aa", 1.0);
+    assert_eq!(line_texts(&layout), [0..5, 5..7]);
+    assert_eq!(layout.lines[0].width, 1.0);
+}
+
+#[test]
+fn words_that_do_not_fit_are_broken() {
+    assert_eq!(line_texts(&wrapped("aaaaa", 1.0)), [0..2, 2..4, 4..5]);
+    // Every line gets at least one glyph, even if it is wider than the line
+    let layout = wrapped("ww", 1.0);
+    assert_eq!(line_texts(&layout), [0..1, 1..2]);
+    assert_eq!(layout.width, 2.0);
+}
+
+#[test]
+fn lines_wrap_after_hyphens() {
+    assert_eq!(line_texts(&wrapped("aa-bb", 1.25)), [0..3, 3..5]);
+    // A hyphen in front of a space is not a break opportunity of its own
+    assert_eq!(line_texts(&wrapped("aa- bb", 1.25)), [0..4, 4..6]);
+}
+
+#[test]
+fn wrapping_keeps_line_breaks() {
+    let layout = wrapped("a\naa aa\n", 1.0);
+    assert_eq!(line_texts(&layout), [0..1, 2..5, 5..7, 8..8]);
+    assert_eq!(layout.lines.iter().map(|line| line.wrapped).collect::<Vec<_>>(), [false, true, false, false]);
+    assert_eq!(wrapped("", 1.0).lines.len(), 1);
+}
+
+fn measured(metrics: TextMetrics) -> (Real, Real, Option<[Real; 4]>, usize) {
+    (metrics.width, metrics.height, metrics.ink_bounds.map(edges), metrics.line_count)
+}
+
+#[test]
+fn measure_matches_layout() {
+    let layouter = layouter(&letters());
+    let metrics = layouter.measure("ab\na", &LayoutOptions::default());
+    assert_eq!(measured(metrics), (1.0, 2.25, Some([0.0, -2.0, 1.0, 0.0]), 2));
+
+    for (text, max_width) in [("ab\na", None), ("aa aa-b ww", Some(1.5)), ("a b\n\nab", Some(0.75))] {
+        let options = LayoutOptions {
+            size: 16.0,
+            max_width,
+            ..Default::default()
+        };
+        let layout = layouter.layout(text, &options);
+        let metrics = layouter.measure(text, &options);
+        assert_eq!((metrics.width, metrics.height, metrics.line_count), (layout.width, layout.height, layout.lines.len()), "{:?}", text);
+        assert_eq!(metrics.ink_bounds.map(edges), quad_bounds(&layout).map(edges), "{:?}", text);
+    }
+}
+
+#[test]
+fn measure_text_without_ink() {
+    let metrics = layouter(&letters()).measure("  ", &LayoutOptions::default());
+    assert_eq!(measured(metrics), (0.5, 1.0, None, 1));
+    let metrics = layouter(&letters()).measure("", &LayoutOptions::default());
+    assert_eq!(measured(metrics), (0.0, 1.0, None, 1));
+}
+
+#[test]
+fn variants_measure_like_layouters() {
+    let variant = letters();
+    let expected = measured(layouter(&variant).measure("ab?", &LayoutOptions {
+        size: 32.0,
+        ..Default::default()
+    }));
+    assert_eq!(measured(variant.measure("ab?", 32.0)), expected);
+
+    let font = ArteryFont {
+        metadata_format: MetadataFormat::None,
+        variants: vec![variant],
+        images: Vec::new(),
+        appendices: Vec::new()
+    };
+    let bytes = write(&font);
+    let font = ArteryFontRef::parse(&bytes).unwrap();
+    assert_eq!(measured(font.variants[0].measure("ab?", 32.0)), expected);
+}
+
+fn aligned(text: &str, align: TextAlign, max_width: Option<Real>) -> TextLayout {
+    layouter(&letters()).layout(text, &LayoutOptions {
+        align,
+        max_width,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn lines_align_within_the_widest_line() {
+    for (align, start) in [(TextAlign::Left, 0.0), (TextAlign::Center, 0.5), (TextAlign::Right, 1.0)] {
+        let layout = aligned("a\naaa", align, None);
+        assert_eq!(layout.lines[0].start, start, "{:?}", align);
+        assert_eq!(layout.lines[1].start, 0.0, "{:?}", align);
+        assert_eq!(layout.glyphs[0].x, start, "{:?}", align);
+        assert_eq!(layout.glyphs[0].quad.unwrap().plane.left, start, "{:?}", align);
+        assert_eq!(layout.width, 1.5);
+    }
+}
+
+#[test]
+fn lines_align_within_max_width() {
+    let layout = aligned("a", TextAlign::Right, Some(2.0));
+    assert_eq!((layout.lines[0].start, layout.glyphs[0].x), (1.5, 1.5));
+    let layout = aligned("a", TextAlign::Center, Some(2.0));
+    assert_eq!((layout.lines[0].start, layout.glyphs[0].x), (0.75, 0.75));
+}
+
+#[test]
+fn justify_widens_whitespace_of_wrapped_lines() {
+    let layout = aligned("a a aaa", TextAlign::Justify, Some(2.0));
+    assert_eq!(line_texts(&layout), [0..4, 4..7]);
+    // The space between the words takes all the extra space, the hanging space keeps its advance
+    assert_eq!(positions(&layout)[..4], [('a', 0.0), (' ', 0.5), ('a', 1.5), (' ', 2.0)]);
+    assert_eq!((layout.glyphs[1].advance, layout.glyphs[3].advance), (1.0, 0.25));
+    assert_eq!(layout.lines[0].width, 2.0);
+    // The last line of the paragraph is aligned left
+    assert_eq!(positions(&layout)[4..], [('a', 0.0), ('a', 0.5), ('a', 1.0)]);
+    assert_eq!(layout.lines[1].width, 1.5);
+}
+
+#[test]
+fn justify_leaves_lines_without_whitespace() {
+    let layout = aligned("aaaaa", TextAlign::Justify, Some(1.25));
+    assert_eq!(line_texts(&layout), [0..2, 2..4, 4..5]);
+    assert!(layout.lines.iter().all(|line| line.start == 0.0 && line.width <= 1.0));
+    assert_eq!(positions(&layout)[..2], [('a', 0.0), ('a', 0.5)]);
+}
+
+fn vertical(variant: &FontVariant, text: &str, max_width: Option<Real>, align: TextAlign) -> TextLayout {
+    layouter(variant).layout(text, &LayoutOptions {
+        writing_mode: WritingMode::VerticalRightToLeft,
+        max_width,
+        align,
+        ..Default::default()
+    })
+}
+
+/// The vertical positions of the glyphs of a layout.
+fn heights(layout: &TextLayout) -> Vec<Real> {
+    layout.glyphs.iter().map(|glyph| glyph.y).collect()
+}
+
+#[test]
+fn vertical_columns_are_stacked_right_to_left() {
+    let layout = vertical(&letters(), "ab\na", None, TextAlign::Left);
+    assert_eq!(layout.writing_mode, WritingMode::VerticalRightToLeft);
+    assert_eq!(layout.lines.iter().map(|line| line.baseline).collect::<Vec<_>>(), [1.875, 0.625]);
+    // Glyphs without a vertical advance advance by one em and are centered on the column
+    assert_eq!(heights(&layout), [-0.75, -1.75, -0.75]);
+    assert_eq!(layout.glyphs.iter().map(|glyph| glyph.x).collect::<Vec<_>>(), [1.625, 1.625, 0.375]);
+    assert_eq!((layout.lines[0].width, layout.lines[1].width), (2.0, 1.0));
+    assert_eq!((layout.width, layout.height), (2.5, 2.0));
+}
+
+#[test]
+fn vertical_advances_and_kerning() {
+    let mut variant = letters();
+    variant.glyphs[2].advance.vertical = 1.5;
+    variant.kern_pairs.push(KernPair {
+        advance: Advance {
+            horizontal: 0.5,
+            vertical: -0.25
+        },
+        ..kern_pair('a', 'a', 0.0)
+    });
+    assert_eq!(heights(&vertical(&variant, "bb", None, TextAlign::Left)), [-1.0, -2.5]);
+    assert_eq!(heights(&vertical(&variant, "aa", None, TextAlign::Left)), [-0.75, -1.5]);
+}
+
+#[test]
+fn vertical_columns_wrap_and_align() {
+    let layout = vertical(&letters(), "aaa", Some(2.0), TextAlign::Left);
+    assert_eq!(line_texts(&layout), [0..2, 2..3]);
+    assert_eq!((layout.width, layout.height), (2.5, 2.0));
+
+    // Right aligns to the bottom of the longest column
+    let layout = vertical(&letters(), "a\naa", None, TextAlign::Right);
+    assert_eq!(layout.lines[0].start, 1.0);
+    assert_eq!(heights(&layout), [-1.75, -0.75, -1.75]);
+}
+
+#[test]
+fn right_to_left_runs_are_reversed() {
+    let text = format!("{}{}", ALEF, BET);
+    let layout = layouter(&letters()).layout(&text, &LayoutOptions::default());
+    assert_eq!(positions(&layout), [(BET, 0.0), (ALEF, 0.5)]);
+    assert_eq!(layout.glyphs.iter().map(|glyph| glyph.index).collect::<Vec<_>>(), [2, 0]);
+    assert!(layout.glyphs.iter().all(|glyph| glyph.right_to_left));
+    assert!(layout.lines[0].right_to_left);
+}
+
+#[test]
+fn right_to_left_runs_inside_left_to_right_paragraphs() {
+    let text = format!("a {}{} b", ALEF, BET);
+    let layout = layouter(&letters()).layout(&text, &LayoutOptions::default());
+    assert_eq!(positions(&layout), [('a', 0.0), (' ', 0.5), (BET, 0.75), (ALEF, 1.25), (' ', 1.75), ('b', 2.0)]);
+    assert_eq!(layout.glyphs.iter().map(|glyph| glyph.right_to_left).collect::<Vec<_>>(), [false, false, true, true, false, false]);
+    assert!(!layout.lines[0].right_to_left);
+}
+
+#[test]
+fn numbers_keep_their_order_in_right_to_left_paragraphs() {
+    let text = format!("{} 12", ALEF);
+    let layout = layouter(&letters()).layout(&text, &LayoutOptions::default());
+    assert_eq!(positions(&layout), [('1', 0.0), ('2', 0.5), (' ', 1.0), (ALEF, 1.25)]);
+    assert!(layout.lines[0].right_to_left);
+}
+
+#[test]
+fn wrapped_right_to_left_lines_hang_whitespace_on_the_left() {
+    let text = format!("{}{} {}{}", ALEF, BET, ALEF, BET);
+    let layout = wrapped(&text, 1.0);
+    assert_eq!(line_texts(&layout), [0..5, 5..9]);
+    assert_eq!(positions(&layout)[..3], [(' ', -0.25), (BET, 0.0), (ALEF, 0.5)]);
+    assert_eq!(layout.lines[0].width, 1.0);
+    assert_eq!(positions(&layout)[3..], [(BET, 0.0), (ALEF, 0.5)]);
+
+    // Right alignment puts the ink of both lines against the right edge
+    let layout = aligned(&text, TextAlign::Right, Some(2.0));
+    assert_eq!(layout.glyphs.iter().filter(|glyph| glyph.character == ALEF).map(|glyph| glyph.x + glyph.advance).collect::<Vec<_>>(), [2.0, 2.0]);
+}
+
+fn caret(layout: &TextLayout, text: &str, index: usize) -> (usize, [Real; 4]) {
+    let caret = layout.caret(text, index);
+    (caret.line, edges(caret.rect))
+}
+
+#[test]
+fn carets_without_lines() {
+    let layout = TextLayout::default();
+    assert_eq!(caret(&layout, "", 0), (0, [0.0; 4]));
+    assert_eq!(caret(&layout, "ab", 1), (0, [0.0; 4]));
+    assert_eq!(layout.hit_test("", 1.0, 1.0), 0);
+}
+
+#[test]
+fn carets_span_their_line() {
+    let text = "ab\nab";
+    let layout = layouter(&letters()).layout(text, &LayoutOptions::default());
+    assert_eq!(caret(&layout, text, 0), (0, [0.0, -1.0, 0.0, 0.0]));
+    assert_eq!(caret(&layout, text, 1), (0, [0.5, -1.0, 0.5, 0.0]));
+    // In front of the line break
+    assert_eq!(caret(&layout, text, 2), (0, [1.0, -1.0, 1.0, 0.0]));
+    assert_eq!(caret(&layout, text, 3), (1, [0.0, -2.25, 0.0, -1.25]));
+    assert_eq!(caret(&layout, text, 100), (1, [1.0, -2.25, 1.0, -1.25]));
+
+    let layout = layouter(&letters()).layout("", &LayoutOptions::default());
+    assert_eq!(caret(&layout, "", 0), (0, [0.0, -1.0, 0.0, 0.0]));
+    assert_eq!(layout.hit_test("", 5.0, -0.5), 0);
+}
+
+#[test]
+fn hit_test_picks_the_closest_line_and_boundary() {
+    let text = "ab\nab";
+    let layout = layouter(&letters()).layout(text, &LayoutOptions::default());
+    assert_eq!(layout.hit_test(text, 0.1, -0.5), 0);
+    assert_eq!(layout.hit_test(text, 0.4, -0.5), 1);
+    assert_eq!(layout.hit_test(text, 5.0, -0.5), 2);
+    assert_eq!(layout.hit_test(text, 0.6, -1.5), 4);
+    assert_eq!(layout.hit_test(text, -5.0, -50.0), 3);
+    assert_eq!(layout.hit_test(text, 5.0, 50.0), 2);
+    for index in 0..=text.len() {
+        let caret = layout.caret(text, index).rect;
+        assert_eq!(layout.hit_test(text, caret.left, caret.bottom + 0.5), index);
+    }
+}
+
+#[test]
+fn combining_marks_stay_with_their_base() {
+    let mut variant = letters();
+    variant.fallback_glyph = 0;
+    let text = "a\u{0301}b";
+    let layout = layouter(&variant).layout(text, &LayoutOptions::default());
+    // Inside the cluster, including inside the mark itself
+    for index in 0..3 {
+        assert_eq!(caret(&layout, text, index).1[0], 0.0, "{}", index);
+    }
+    assert_eq!(caret(&layout, text, 3).1[0], 0.5);
+    assert_eq!(layout.hit_test(text, 0.4, -0.5), 3);
+    assert_eq!(layout.hit_test(text, 0.1, -0.5), 0);
+}
+
+#[test]
+fn hit_test_stops_in_front_of_hanging_whitespace() {
+    let text = "aa aa";
+    let layout = wrapped(text, 1.0);
+    assert_eq!(layout.hit_test(text, 5.0, -0.5), 2);
+    assert_eq!(layout.hit_test(text, 5.0, -1.5), 5);
+    assert_eq!(caret(&layout, text, 3), (1, [0.0, -2.25, 0.0, -1.25]));
+}
+
+#[test]
+fn right_to_left_carets_start_on_the_right() {
+    let text = format!("{}{}", ALEF, BET);
+    let layout = layouter(&letters()).layout(&text, &LayoutOptions::default());
+    assert_eq!(caret(&layout, &text, 0).1[0], 1.0);
+    assert_eq!(caret(&layout, &text, 2).1[0], 0.5);
+    assert_eq!(caret(&layout, &text, 4).1[0], 0.0);
+    assert_eq!(layout.hit_test(&text, 0.9, -0.5), 0);
+    assert_eq!(layout.hit_test(&text, 0.6, -0.5), 2);
+    assert_eq!(layout.hit_test(&text, 0.1, -0.5), 4);
+}
+
+#[test]
+fn vertical_carets_span_their_column() {
+    let text = "ab";
+    let layout = vertical(&letters(), text, None, TextAlign::Left);
+    assert_eq!(caret(&layout, text, 0), (0, [0.125, 0.0, 1.125, 0.0]));
+    assert_eq!(caret(&layout, text, 1), (0, [0.125, -1.0, 1.125, -1.0]));
+    assert_eq!(caret(&layout, text, 2), (0, [0.125, -2.0, 1.125, -2.0]));
+    assert_eq!(layout.hit_test(text, 0.6, -0.9), 1);
+    assert_eq!(layout.hit_test(text, 0.6, -5.0), 2);
+}
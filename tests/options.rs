@@ -0,0 +1,183 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+/// A font with a single raw 2x3 image whose rows, from top to bottom, are `[0, 1]`, `[2, 3]` and `[4, 5]`.
+fn raw_font(orientation: ImageOrientation) -> ArteryFont {
+    let data = match orientation {
+        ImageOrientation::TopDown => vec![0, 1, 2, 3, 4, 5],
+        _ => vec![4, 5, 2, 3, 0, 1]
+    };
+    ArteryFont {
+        metadata_format: MetadataFormat::None,
+        variants: Vec::new(),
+        images: vec![Image {
+            flags: 0,
+            width: 2,
+            height: 3,
+            channels: 1,
+            pixel_format: PixelFormat::Unsigned8,
+            image_type: ImageType::SrgbImage,
+            child_images: 0,
+            texture_flags: 0,
+            metadata: String::new(),
+            data,
+            orientation,
+            encoded: None
+        }],
+        appendices: Vec::new()
+    }
+}
+
+fn with_orientation(orientation: ImageOrientation) -> ReadOptions {
+    ReadOptions {
+        orientation,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn orientation_survives_write() {
+    let bytes = write(&raw_font(ImageOrientation::BottomUp));
+    for orientation in [ImageOrientation::TopDown, ImageOrientation::BottomUp] {
+        let mut font = ArteryFont::read_with(&bytes[..], with_orientation(orientation)).unwrap();
+        assert_eq!(font.images[0].orientation, orientation);
+        assert_eq!(font.images[0].data, raw_font(orientation).images[0].data);
+
+        font.images[0].encoded = None;
+        let rewritten = write(&font);
+        for read_as in [ImageOrientation::TopDown, ImageOrientation::BottomUp] {
+            let font = ArteryFont::read_with(&rewritten[..], with_orientation(read_as)).unwrap();
+            assert_eq!(font.images[0].data, raw_font(read_as).images[0].data, "written {:?}, read {:?}", orientation, read_as);
+        }
+    }
+}
+
+#[test]
+fn decode_uses_read_orientation() {
+    let bytes = write(&raw_font(ImageOrientation::BottomUp));
+    for orientation in [ImageOrientation::TopDown, ImageOrientation::BottomUp] {
+        let options = ReadOptions {
+            decode_images: false,
+            ..with_orientation(orientation)
+        };
+        let mut font = ArteryFont::read_with(&bytes[..], options).unwrap();
+        assert!(font.images[0].data.is_empty());
+        assert_eq!(font.images[0].decode().unwrap(), raw_font(orientation).images[0].data);
+
+        let mut font = ArteryFontRef::parse_with(&bytes, options).unwrap();
+        assert_eq!(font.images[0].decode().unwrap(), &raw_font(orientation).images[0].data);
+    }
+}
+
+#[test]
+fn raw_image_with_incomplete_row() {
+    let mut font = raw_font(ImageOrientation::TopDown);
+    font.images[0].data.clear();
+    font.images[0].encoded = Some(EncodedImage {
+        encoding: ImageEncoding::RawBinary,
+        orientation: ImageOrientation::TopDown,
+        row_length: 2,
+        data: vec![0, 1, 2, 3, 4],
+        decoded_checksum: None
+    });
+    let bytes = write(&font);
+    for orientation in [ImageOrientation::TopDown, ImageOrientation::BottomUp] {
+        let err = ArteryFont::read_with(&bytes[..], with_orientation(orientation)).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidImage { encoding: ImageEncoding::RawBinary, .. }), "{}", err);
+        assert_eq!(err.location().map(|location| location.section), Some(Section::Image(0)));
+    }
+}
+
+fn limit(err: &Error) -> (usize, usize) {
+    match err.kind() {
+        &ErrorKind::AllocationLimit { requested, limit } => (requested, limit),
+        _ => panic!("expected an allocation limit error, got {}", err)
+    }
+}
+
+#[test]
+fn default_limits_reject_huge_sections() {
+    let mut bytes = write(&raw_font(ImageOrientation::BottomUp));
+    // images_length
+    bytes[68..72].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = ArteryFont::read(&bytes[..]).unwrap_err();
+    assert_eq!(limit(&err), (u32::MAX as usize, 1 << 30));
+    assert_eq!(err.location().map(|location| location.section), Some(Section::Images));
+}
+
+#[test]
+fn default_limits_reject_huge_images() {
+    let mut font = raw_font(ImageOrientation::BottomUp);
+    let image = &mut font.images[0];
+    image.data.clear();
+    image.width = 1 << 16;
+    image.height = 1 << 16;
+    image.encoded = Some(EncodedImage {
+        encoding: ImageEncoding::RawBinary,
+        orientation: ImageOrientation::TopDown,
+        row_length: 1 << 16,
+        data: vec![0; 16],
+        decoded_checksum: None
+    });
+    let bytes = write(&font);
+    let err = ArteryFont::read(&bytes[..]).unwrap_err();
+    assert_eq!(limit(&err), (1 << 32, 256 << 20));
+    assert_eq!(err.location().map(|location| location.section), Some(Section::Image(0)));
+
+    let mut font = ArteryFont::read_lazy(&bytes[..]).unwrap();
+    let err = font.images[0].decode().unwrap_err();
+    assert_eq!(limit(&err), (1 << 32, 256 << 20));
+    assert_eq!(err.location(), None);
+}
+
+#[test]
+fn allocation_limits() {
+    let mut font = raw_font(ImageOrientation::BottomUp);
+    font.images[0].width = 1000;
+    font.images[0].height = 1000;
+    font.images[0].data = vec![0; 1000 * 1000];
+    font.images.push(font.images[0].clone());
+    let bytes = write(&font);
+    let read = |max_allocation, max_total_allocation| ArteryFont::read_with(&bytes[..], ReadOptions {
+        max_allocation,
+        max_total_allocation,
+        ..Default::default()
+    });
+
+    assert!(read(1_000_000, 5_000_000).is_ok());
+    let err = read(999_999, 5_000_000).unwrap_err();
+    assert_eq!(limit(&err), (1_000_000, 999_999));
+    assert_eq!(err.location().map(|location| location.section), Some(Section::Image(0)));
+
+    // Every image allocates its raw bytes and the decoded copy
+    let err = read(1_000_000, 3_000_000).unwrap_err();
+    assert_eq!(err.location().map(|location| location.section), Some(Section::Image(1)));
+    assert_eq!(limit(&err).1, 3_000_000);
+}
+
+#[test]
+fn borrowed_data_is_not_charged() {
+    let mut font = raw_font(ImageOrientation::BottomUp);
+    font.images[0].width = 1000;
+    font.images[0].height = 1000;
+    font.images[0].data = vec![0; 1000 * 1000];
+    let bytes = write(&font);
+    let options = ReadOptions {
+        decode_images: false,
+        max_allocation: 500_000,
+        ..Default::default()
+    };
+
+    let parsed = ArteryFontRef::parse_with(&bytes, options).unwrap();
+    assert_eq!(parsed.images[0].encoded.as_ref().map(|encoded| encoded.data.len()), Some(1000 * 1000));
+
+    let err = ArteryFont::read_with(&bytes[..], options).unwrap_err();
+    assert_eq!(limit(&err), (1_000_000, 500_000));
+
+    let mut parser = ArteryFontParser::with_options(options);
+    parser.feed(&bytes);
+    let err = std::iter::from_fn(|| parser.poll().transpose()).find_map(Result::err).unwrap();
+    assert_eq!(limit(&err), (1_000_000, 500_000));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+/// Feeds `bytes` in chunks of `chunk_size` and assembles the events into a font.
+fn parse(bytes: &[u8], chunk_size: usize) -> Result<ArteryFont, Error> {
+    let mut parser = ArteryFontParser::with_options(lazy());
+    let mut font = ArteryFont {
+        metadata_format: MetadataFormat::None,
+        variants: Vec::new(),
+        images: Vec::new(),
+        appendices: Vec::new()
+    };
+    for chunk in bytes.chunks(chunk_size) {
+        parser.feed(chunk);
+        while let Some(event) = parser.poll()? {
+            match event {
+                ParseEvent::Header { metadata_format, .. } => font.metadata_format = metadata_format,
+                ParseEvent::Variant(i, variant) => {
+                    assert_eq!(i, font.variants.len());
+                    font.variants.push(variant)
+                },
+                ParseEvent::Image(i, image) => {
+                    assert_eq!(i, font.images.len());
+                    font.images.push(image)
+                },
+                ParseEvent::Appendix(i, appendix) => {
+                    assert_eq!(i, font.appendices.len());
+                    font.appendices.push(appendix)
+                },
+                ParseEvent::Done => assert!(parser.is_done())
+            }
+        }
+    }
+    assert!(parser.is_done());
+    assert!(parser.poll()?.is_none());
+    Ok(font)
+}
+
+#[test]
+fn parser_matches_read() {
+    let bytes = sample_font();
+    let font = format!("{:?}", ArteryFont::read_with(&bytes[..], lazy()).unwrap());
+    for chunk_size in [1, 3, 64, 1000, bytes.len()] {
+        assert_eq!(format!("{:?}", parse(&bytes, chunk_size).unwrap()), font, "chunks of {} bytes", chunk_size);
+    }
+}
+
+#[test]
+fn parser_reports_errors_like_read() {
+    let options = ReadOptions {
+        verify_checksum: true,
+        ..lazy()
+    };
+    let bytes = sample_font();
+    let len = bytes.len();
+    let font = ArteryFontRef::parse_with(&bytes, lazy()).unwrap();
+    let name = font.variants[1].name.as_ptr() as usize - bytes.as_ptr() as usize;
+    // The header tag, the name of the second variant, the footer magic number and the checksum
+    for (offset, value) in [(0, [0xff; 4]), (name, [0xff; 4]), (len - 28, [0; 4]), (len - 4, [0x78, 0x56, 0x34, 0x12])] {
+        let mut bytes = bytes.clone();
+        bytes[offset..offset + 4].copy_from_slice(&value);
+        let expected = ArteryFont::read_with(&bytes[..], options).unwrap_err();
+
+        let mut parser = ArteryFontParser::with_options(options);
+        parser.feed(&bytes);
+        let err = loop {
+            match parser.poll() {
+                Ok(Some(_)) => {},
+                Ok(None) => panic!("no error at {}", offset),
+                Err(err) => break err
+            }
+        };
+        assert_eq!(format!("{:?}", err.kind()), format!("{:?}", expected.kind()), "at {}", offset);
+        assert_eq!(err.location(), expected.location(), "at {}", offset);
+    }
+}
+
+#[test]
+fn parser_waits_for_truncated_files() {
+    let bytes = sample_font();
+    let mut parser = ArteryFontParser::with_options(lazy());
+    parser.feed(&bytes[..bytes.len() - 1]);
+    while parser.poll().unwrap().is_some() {}
+    assert!(!parser.is_done());
+    parser.feed(&bytes[bytes.len() - 1..]);
+    assert!(matches!(parser.poll().unwrap(), Some(ParseEvent::Done)));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::io::{Cursor, Seek, SeekFrom};
+use artery_font::*;
+use common::*;
+
+#[test]
+fn reader_matches_read() {
+    let bytes = sample_font();
+    let font = ArteryFont::read_with(&bytes[..], lazy()).unwrap();
+    let mut reader = ArteryFontReader::with_options(Cursor::new(&bytes), lazy()).unwrap();
+
+    let summary = reader.summary().clone();
+    assert_eq!((summary.variants.len(), summary.images.len(), summary.appendix_count), (2, 2, 2));
+    assert_eq!(format!("{:?}", summary), format!("{:?}", ArteryFont::probe(Cursor::new(&bytes)).unwrap()));
+
+    // Out of order, to make sure every item is located on its own
+    for i in (0..3).rev() {
+        assert_eq!(format!("{:?}", reader.appendix(i).unwrap()), format!("{:?}", font.appendices.get(i)));
+        assert_eq!(format!("{:?}", reader.image(i).unwrap()), format!("{:?}", font.images.get(i)));
+        assert_eq!(format!("{:?}", reader.variant(i).unwrap()), format!("{:?}", font.variants.get(i)));
+    }
+    assert_eq!(reader.variant_by_name("Bold").unwrap().unwrap().weight, 700);
+    assert!(reader.variant_by_name("Italic").unwrap().is_none());
+}
+
+#[test]
+fn reader_starts_at_stream_position() {
+    let mut bytes = vec![0xff; 5];
+    bytes.extend(sample_font());
+    let mut cursor = Cursor::new(bytes);
+    cursor.seek(SeekFrom::Start(5)).unwrap();
+    let mut reader = ArteryFontReader::with_options(cursor, lazy()).unwrap();
+    let font = ArteryFont::read_with(&sample_font()[..], lazy()).unwrap();
+    assert_eq!(format!("{:?}", reader.appendix(1).unwrap()), format!("{:?}", font.appendices.get(1)));
+    assert_eq!(format!("{:?}", reader.variant(0).unwrap()), format!("{:?}", font.variants.first()));
+}
+
+#[test]
+fn reader_reports_corrupt_items() {
+    let bytes = sample_font();
+    let font = ArteryFontRef::parse_with(&bytes, lazy()).unwrap();
+    // Invalid utf-8 in the metadata of the first appendix, which the reader does not look at until the appendix is loaded
+    let offset = font.appendices[0].metadata.as_ptr() as usize - bytes.as_ptr() as usize;
+    let mut corrupt = bytes.clone();
+    corrupt[offset] = 0xff;
+    let mut reader = ArteryFontReader::with_options(Cursor::new(corrupt), lazy()).unwrap();
+    assert!(reader.appendix(1).unwrap().is_some());
+    let err = reader.appendix(0).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Utf8(_)), "{}", err);
+    assert_eq!(err.location().map(|location| location.section), Some(Section::Appendix(0)));
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use artery_font::*;
+use common::*;
+
+/// A font with a single 2x2 raw image that is stored top-down, so decoding it into bottom-up rows changes its bytes.
+fn top_down_font() -> Vec<u8> {
+    let font = ArteryFont {
+        metadata_format: MetadataFormat::None,
+        variants: Vec::new(),
+        images: vec![Image {
+            flags: 0,
+            width: 2,
+            height: 2,
+            channels: 1,
+            pixel_format: PixelFormat::Unsigned8,
+            image_type: ImageType::SrgbImage,
+            child_images: 0,
+            texture_flags: 0,
+            metadata: String::new(),
+            data: Vec::new(),
+            orientation: ImageOrientation::BottomUp,
+            encoded: Some(EncodedImage {
+                encoding: ImageEncoding::RawBinary,
+                orientation: ImageOrientation::TopDown,
+                row_length: 2,
+                data: vec![1, 2, 3, 4],
+                decoded_checksum: None
+            })
+        }],
+        appendices: Vec::new()
+    };
+    write(&font)
+}
+
+#[test]
+fn eager_reads_keep_encoded_images() {
+    let bytes = top_down_font();
+    let font = ArteryFont::read(&bytes[..]).unwrap();
+    let image = &font.images[0];
+    assert_eq!(image.data, [3, 4, 1, 2]);
+    let encoded = image.encoded.as_ref().unwrap();
+    assert_eq!((encoded.encoding, encoded.orientation, encoded.row_length), (ImageEncoding::RawBinary, ImageOrientation::TopDown, 2));
+    assert_eq!(encoded.data, [1, 2, 3, 4]);
+    assert!(encoded.decoded_checksum.is_some());
+    assert_eq!(write(&font), bytes);
+
+    let font = ArteryFontRef::parse(&bytes).unwrap();
+    assert_eq!(*font.images[0].encoded.as_ref().unwrap().data, [1, 2, 3, 4]);
+    assert_eq!(write(&font.into_owned()), bytes);
+}
+
+#[test]
+fn edited_images_are_not_dropped() {
+    let bytes = top_down_font();
+    let mut eager = ArteryFont::read(&bytes[..]).unwrap();
+    eager.images[0].data[0] = 9;
+    let mut lazy = ArteryFont::read_lazy(&bytes[..]).unwrap();
+    lazy.images[0].decode().unwrap();
+    lazy.images[0].data[0] = 9;
+
+    for font in [eager, lazy] {
+        let written = write(&font);
+        assert_ne!(written, bytes);
+        let font = ArteryFont::read(&written[..]).unwrap();
+        assert_eq!(font.images[0].data, [9, 4, 1, 2]);
+        assert_eq!(font.images[0].encoded.as_ref().unwrap().encoding, ImageEncoding::RawBinary);
+    }
+}
+
+#[test]
+fn undecoded_images_with_data_are_written_raw() {
+    let bytes = top_down_font();
+    let mut font = ArteryFont::read_lazy(&bytes[..]).unwrap();
+    assert_eq!(font.images[0].encoded.as_ref().unwrap().decoded_checksum, None);
+    // Matches the encoded image, but was not produced by decoding it
+    font.images[0].data = vec![3, 4, 1, 2];
+    let written = ArteryFont::read(&write(&font)[..]).unwrap();
+    assert_eq!(written.images[0].data, [3, 4, 1, 2]);
+    assert_eq!(written.images[0].encoded.as_ref().unwrap().encoding, ImageEncoding::RawBinary);
+}
+
+#[test]
+fn float_images_are_little_endian_in_the_file() {
+    let samples = [1.5f32, -2.0];
+    let mut font = ArteryFont::read_lazy(&top_down_font()[..]).unwrap();
+    let image = &mut font.images[0];
+    image.width = 2;
+    image.height = 1;
+    image.pixel_format = PixelFormat::Float32;
+    let native: Vec<u8> = samples.iter().flat_map(|sample| sample.to_ne_bytes()).collect();
+    image.data = native.clone();
+    image.encoded = None;
+    let bytes = write(&font);
+    let little_endian: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    assert!(bytes.windows(little_endian.len()).any(|window| window == little_endian));
+
+    let font = ArteryFont::read(&bytes[..]).unwrap();
+    assert_eq!(font.images[0].data, native);
+}
+
+#[test]
+fn round_trip() {
+    let font = ArteryFont::read_lazy(TEST_FONT).unwrap();
+    let bytes = write(&font);
+    assert_eq!(format!("{:?}", ArteryFont::read_lazy(&bytes[..]).unwrap()), format!("{:?}", font));
+    let font = ArteryFontRef::parse_lazy(TEST_FONT).unwrap();
+    assert_eq!(write(&font.into_owned()), bytes);
+    // The test font stores f32 reals, which the `double` feature writes as f64
+    if cfg!(not(feature = "double")) {
+        assert_eq!(bytes, TEST_FONT);
+    }
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn round_trip_decoded() {
+    let font = ArteryFont::read(TEST_FONT).unwrap();
+    assert!(!font.images[0].data.is_empty());
+    assert_eq!(write(&font), write(&ArteryFont::read_lazy(TEST_FONT).unwrap()));
+
+    let mut raw = font.clone();
+    raw.images[0].encoded = None;
+    let font = ArteryFont::read(&write(&raw)[..]).unwrap();
+    assert_eq!(font.images[0].encoded.as_ref().unwrap().encoding, ImageEncoding::RawBinary);
+    assert_eq!(font.images[0].data, raw.images[0].data);
+    assert_eq!(format!("{:?}", font.variants), format!("{:?}", raw.variants));
+}